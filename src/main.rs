@@ -1,26 +1,36 @@
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use rand::{distributions::Distribution, seq::SliceRandom, thread_rng, Rng};
+use rand::{distributions::Distribution, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use ratatui::{
     backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
-    Terminal,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Cell, Gauge, Paragraph, Row, Table, TableState, Wrap},
+    Frame, Terminal, TerminalOptions, Viewport,
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_width::UnicodeWidthChar;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
+    ops::{Deref, DerefMut},
     fs,
-    io::{self},
+    fmt::Display,
+    io::{self, ErrorKind, IsTerminal, Read},
+    ops::RangeInclusive,
     process::{Command as SysCommand, Stdio},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
     time::{Duration, Instant},
 };
 
@@ -28,9 +38,35 @@ const DEFAULT_WORDS_STR: &str = "the be to of and a in that have I it for not on
 
 // --- Gum Integration Wrappers ---
 
+const GUM_CANCELLED: i32 = 130; // Exit code gum uses when the user aborts with Esc/Ctrl+C
+
+// Cancelling is a normal answer (empty output); any other failure is an error, so a broken
+// gum install doesn't look like the user picking "Back".
+fn check_gum_status(command: &str, status: std::process::ExitStatus, stderr: &[u8]) -> Result<()> {
+    if status.success() || status.code() == Some(GUM_CANCELLED) {
+        return Ok(());
+    }
+    let detail = String::from_utf8_lossy(stderr);
+    anyhow::bail!("gum {} failed ({}){}", command, status, if detail.trim().is_empty() { String::new() } else { format!(": {}", detail.trim()) })
+}
+
 fn gum_choose(header: &str, options: &[&str]) -> Result<String> {
-    let child = SysCommand::new("gum")
-        .arg("choose")
+    gum_choose_at(header, options, None)
+}
+
+// Same, with the cursor starting on `selected` instead of the first option
+fn gum_choose_at(header: &str, options: &[&str], selected: Option<&str>) -> Result<String> {
+    Ok(gum_choose_many(header, options, 1, selected)?.into_iter().next().unwrap_or_default())
+}
+
+fn gum_choose_many(header: &str, options: &[&str], limit: usize, selected: Option<&str>) -> Result<Vec<String>> {
+    let mut command = SysCommand::new("gum");
+    command.arg("choose");
+    if let Some(selected) = selected.filter(|s| options.contains(s)) {
+        command.arg("--selected").arg(selected);
+    }
+    let child = command
+        .arg("--limit").arg(limit.to_string())
         .arg("--item.foreground").arg("240")     // Dark Grey text when unselected
         .arg("--selected.foreground").arg("255") // White text when selected
         .arg("--cursor.foreground").arg("#07CE41")   // cursor
@@ -59,8 +95,14 @@ fn gum_choose(header: &str, options: &[&str]) -> Result<String> {
         .status();
 
     let output = child.wait_with_output()?;
+    // stderr went to the terminal, gum's own message is already on screen
+    check_gum_status("choose", output.status, &output.stderr)?;
 
-    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    Ok(String::from_utf8(output.stdout)?
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
 }
 
 fn gum_input(header: &str, placeholder: &str, value: &str) -> Result<String> {
@@ -74,10 +116,31 @@ fn gum_input(header: &str, placeholder: &str, value: &str) -> Result<String> {
         .arg(value)
         .output()
         .context("Failed to execute gum")?;
+    check_gum_status("input", output.status, &output.stderr)?;
 
     Ok(String::from_utf8(output.stdout)?.trim().to_string())
 }
 
+// Re-prompts until the input parses and lies in `range`. None when the field is left empty or cancelled.
+fn gum_input_number<T>(header: &str, placeholder: &str, current: T, range: RangeInclusive<T>) -> Result<Option<T>>
+where
+    T: FromStr + PartialOrd + Display,
+{
+    let header = format!("{} ({} to {})", header, range.start(), range.end());
+    let mut value = current.to_string();
+    loop {
+        let val = gum_input(&header, placeholder, &value)?;
+        if val.is_empty() {
+            return Ok(None);
+        }
+        match val.parse::<T>() {
+            Ok(n) if range.contains(&n) => return Ok(Some(n)),
+            _ => gum_error(&format!("'{}' isn't a number from {} to {}", val, range.start(), range.end())),
+        }
+        value = val;
+    }
+}
+
 fn gum_confirm(prompt: &str) -> bool {
     SysCommand::new("gum")
         .arg("confirm")
@@ -103,16 +166,453 @@ fn gum_style(text: &str) -> Result<()> {
     Ok(())
 }
 
+fn gum_error(text: &str) {
+    let _ = SysCommand::new("gum")
+        .arg("style")
+        .arg("--foreground").arg("196")        // Red text
+        .arg("--border").arg("rounded")
+        .arg("--border-foreground").arg("196")
+        .arg("--padding").arg("0 2")
+        .arg(text)
+        .status();
+}
+
+// Pause for user
+fn wait_for_enter() {
+    let _ = SysCommand::new("gum").arg("format").arg("Press Enter...").status();
+    let _ = std::io::stdin().read_line(&mut String::new());
+}
+
+// --- Logging ---
+
+const LOG_FILE: &str = "musical-typing.log";
+
+// The TUI owns stdout, so events go to the log file, one timestamped line each.
+// Arguments aren't formatted at all when logging is off.
+macro_rules! log_event {
+    ($($arg:tt)*) => {
+        if let Some(log) = LOG.get() {
+            if let Ok(mut file) = log.lock() {
+                use std::io::Write as _;
+                let _ = writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), format_args!($($arg)*));
+            }
+        }
+    };
+}
+
+// Set once by init_logging; unset means logging is off and log_event! does nothing
+static LOG: OnceLock<Mutex<fs::File>> = OnceLock::new();
+
+// On with --verbose or any non-empty RUST_LOG. Appends, so earlier sessions stay for comparison.
+fn init_logging(verbose: bool) -> Result<()> {
+    let from_env = std::env::var("RUST_LOG").map(|v| !v.trim().is_empty()).unwrap_or(false);
+    if !verbose && !from_env {
+        return Ok(());
+    }
+    let file = fs::OpenOptions::new().create(true).append(true).open(LOG_FILE).with_context(|| format!("Failed to open {}", LOG_FILE))?;
+    let _ = LOG.set(Mutex::new(file));
+    log_event!("session start, typr-rs {}", env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
+
 // --- Data Structures ---
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum PastePolicy {
+    Ignore,     // Drop pasted text, the test carries on
+    Invalidate, // Accept nothing, but the result is never saved
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum ScrollPolicy {
+    Center, // Keep the cursor row in the middle of the text area
+    Margin, // Only scroll once the cursor gets within `scroll_margin` rows of the bottom
+}
+
+// Colours of the header and footer bars on the TUI screens
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum Theme {
+    Violet,
+    Ocean,
+    Forest,
+    Ember,
+    Mono,
+}
+
+impl Theme {
+    fn next(self) -> Self {
+        match self {
+            Theme::Violet => Theme::Ocean,
+            Theme::Ocean => Theme::Forest,
+            Theme::Forest => Theme::Ember,
+            Theme::Ember => Theme::Mono,
+            Theme::Mono => Theme::Violet,
+        }
+    }
+
+    fn header(self) -> Style {
+        let (fg, bg) = match self {
+            Theme::Violet => (Color::White, Color::Rgb(46, 2, 91)),
+            Theme::Ocean => (Color::Rgb(220, 240, 255), Color::Rgb(10, 50, 90)),
+            Theme::Forest => (Color::Rgb(230, 250, 230), Color::Rgb(20, 70, 35)),
+            Theme::Ember => (Color::Rgb(255, 230, 200), Color::Rgb(110, 30, 10)),
+            Theme::Mono => (Color::White, Color::Rgb(60, 60, 60)),
+        };
+        Style::default().fg(themed("HEADER_FG", fg)).bg(themed("HEADER_BG", bg)).add_modifier(Modifier::BOLD)
+    }
+
+    fn footer(self) -> Style {
+        let (fg, bg) = match self {
+            Theme::Violet | Theme::Mono => (Color::Gray, Color::Black),
+            Theme::Ocean => (Color::Rgb(150, 180, 200), Color::Rgb(5, 20, 35)),
+            Theme::Forest => (Color::Rgb(160, 200, 160), Color::Rgb(8, 25, 12)),
+            Theme::Ember => (Color::Rgb(210, 160, 120), Color::Rgb(30, 10, 5)),
+        };
+        Style::default().fg(themed("FOOTER_FG", fg)).bg(themed("FOOTER_BG", bg))
+    }
+}
+
+// MT_COLOR_<SLOT>=green or =#ff8800 replaces one colour whatever the theme, for quick experiments
+const COLOR_SLOTS: [&str; 8] = ["CORRECT", "INCORRECT", "CURSOR", "UPCOMING", "HEADER_FG", "HEADER_BG", "FOOTER_FG", "FOOTER_BG"];
+
+// Read once: styles are built for every character on every frame
+fn color_overrides() -> &'static HashMap<String, Color> {
+    static OVERRIDES: OnceLock<HashMap<String, Color>> = OnceLock::new();
+    OVERRIDES.get_or_init(|| {
+        std::env::vars_os()
+            .filter_map(|(key, value)| {
+                let slot = key.to_str()?.strip_prefix("MT_COLOR_")?;
+                Some((slot.to_string(), value.to_str()?.parse().ok()?))
+            })
+            .collect()
+    })
+}
+
+fn themed(slot: &str, default: Color) -> Color {
+    color_overrides().get(slot).copied().unwrap_or(default)
+}
+
+// Unknown slots and unparsable colours, reported with the other load errors
+fn color_override_errors() -> Vec<String> {
+    let mut errors = Vec::new();
+    for (key, value) in std::env::vars_os() {
+        let Some(slot) = key.to_str().and_then(|k| k.strip_prefix("MT_COLOR_")) else {
+            continue;
+        };
+        let value = value.to_string_lossy();
+        if !COLOR_SLOTS.contains(&slot) {
+            errors.push(format!("MT_COLOR_{} isn't a colour slot (try one of {})", slot, COLOR_SLOTS.join(", ")));
+        } else if value.parse::<Color>().is_err() {
+            errors.push(format!("MT_COLOR_{}: '{}' isn't a colour name or #rrggbb", slot, value));
+        }
+    }
+    errors
+}
+
+// --- Key Bindings ---
+
+// Action -> key name as written in settings.json: "esc", "tab", "backspace", "f5", "ctrl+r", ...
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+struct KeyBindings {
+    quit: String,
+    regenerate: String, // New text, before the first keystroke only
+    delete: String,
+    toggle_forgive: String, // Flips forgive-errors for the session, without saving it
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            quit: "esc".to_string(),
+            regenerate: "tab".to_string(),
+            delete: "backspace".to_string(),
+            toggle_forgive: "f2".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        let (modifiers, key) = match name.strip_prefix("ctrl+") {
+            Some(rest) => (KeyModifiers::CONTROL, rest),
+            None => (KeyModifiers::NONE, name.as_str()),
+        };
+        let code = match key {
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "enter" => KeyCode::Enter,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            f if f.starts_with('f') && f.len() > 1 => KeyCode::F(f[1..].parse().ok().filter(|n| (1..=12).contains(n))?),
+            c if c.chars().count() == 1 => KeyCode::Char(c.chars().next()?),
+            _ => return None,
+        };
+        Some(Self { code, modifiers })
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.contains(self.modifiers)
+    }
+}
+
+// Resolved once per screen so key events aren't parsing strings
+struct BoundKeys {
+    quit: KeyBinding,
+    regenerate: KeyBinding,
+    delete: KeyBinding,
+    toggle_forgive: KeyBinding,
+}
+
+impl KeyBindings {
+    // Unknown names, keys that would be typed as text, Ctrl+C and duplicates are all rejected
+    fn resolve(&self) -> Result<BoundKeys, String> {
+        let actions = [
+            ("quit", &self.quit),
+            ("regenerate", &self.regenerate),
+            ("delete", &self.delete),
+            ("toggle_forgive", &self.toggle_forgive),
+        ];
+        let mut bound = Vec::new();
+        for (action, name) in actions {
+            let key = KeyBinding::parse(name).ok_or_else(|| format!("unknown key '{}' for {}", name, action))?;
+            if let KeyCode::Char(c) = key.code {
+                if key.modifiers.is_empty() {
+                    return Err(format!("'{}' for {} would swallow typed text, use a key like ctrl+{}", name, action, c));
+                }
+                if c == 'c' {
+                    return Err(format!("ctrl+c for {} is reserved for interrupting", action));
+                }
+            }
+            if let Some((other, _)) = bound.iter().find(|(_, k)| *k == key) {
+                return Err(format!("{} and {} both use '{}'", other, action, name));
+            }
+            bound.push((action, key));
+        }
+        Ok(BoundKeys { quit: bound[0].1, regenerate: bound[1].1, delete: bound[2].1, toggle_forgive: bound[3].1 })
+    }
+}
+
+// How Words mode progress reads in the status bar
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum ProgressFormat {
+    Remaining, // "13 left"
+    Count,     // "12/25 words"
+    Percent,   // "48%"
+}
+
+impl ProgressFormat {
+    fn next(self) -> Self {
+        match self {
+            ProgressFormat::Remaining => ProgressFormat::Count,
+            ProgressFormat::Count => ProgressFormat::Percent,
+            ProgressFormat::Percent => ProgressFormat::Remaining,
+        }
+    }
+
+    fn render(self, done: usize, total: usize) -> String {
+        match self {
+            ProgressFormat::Remaining => format!("{} left", total.saturating_sub(done)),
+            ProgressFormat::Count => format!("{}/{} words", done.min(total), total),
+            ProgressFormat::Percent => format!("{:.0}%", done.min(total) as f64 / total.max(1) as f64 * 100.0),
+        }
+    }
+}
+
+// How numbers on the stats and results screens are written
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum NumberFormat {
+    Auto,  // From LC_ALL / LC_NUMERIC / LANG
+    Plain, // 1234.5
+    Comma, // 1,234.5
+    Dot,   // 1.234,5
+    Space, // 1 234,5
+}
+
+impl NumberFormat {
+    fn next(self) -> Self {
+        match self {
+            NumberFormat::Auto => NumberFormat::Plain,
+            NumberFormat::Plain => NumberFormat::Comma,
+            NumberFormat::Comma => NumberFormat::Dot,
+            NumberFormat::Dot => NumberFormat::Space,
+            NumberFormat::Space => NumberFormat::Auto,
+        }
+    }
+
+    // Auto guesses from the language part of the locale; C/POSIX or unset stays Plain
+    fn resolve(self) -> Self {
+        if self != NumberFormat::Auto {
+            return self;
+        }
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"].iter().filter_map(|v| std::env::var(v).ok()).find(|v| !v.is_empty()).unwrap_or_default();
+        match locale.split(['_', '.', '@']).next().unwrap_or_default() {
+            "en" => NumberFormat::Comma,
+            "de" | "es" | "it" | "nl" | "pt" | "da" | "tr" | "id" => NumberFormat::Dot,
+            "fr" | "ru" | "sv" | "pl" | "cs" | "fi" | "nb" | "uk" => NumberFormat::Space,
+            _ => NumberFormat::Plain,
+        }
+    }
+
+    fn float(self, value: f64, decimals: usize) -> String {
+        let (thousands, decimal) = match self.resolve() {
+            NumberFormat::Comma => (Some(','), '.'),
+            NumberFormat::Dot => (Some('.'), ','),
+            NumberFormat::Space => (Some(' '), ','),
+            NumberFormat::Plain | NumberFormat::Auto => (None, '.'),
+        };
+        let plain = format!("{:.*}", decimals, value);
+        let (sign, plain) = plain.strip_prefix('-').map_or(("", plain.as_str()), |p| ("-", p));
+        let (int_part, frac_part) = plain.split_once('.').unwrap_or((plain, ""));
+        let mut text = String::from(sign);
+        for (i, digit) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                text.extend(thousands);
+            }
+            text.push(digit);
+        }
+        if !frac_part.is_empty() {
+            text.push(decimal);
+            text.push_str(frac_part);
+        }
+        text
+    }
+
+    fn int(self, n: usize) -> String {
+        self.float(n as f64, 0)
+    }
+}
+
+// Ordering applied once per session to the list that sequential order walks
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+enum ListOrder {
+    AsIs,
+    Shuffled,
+    Alphabetical,
+    ReverseAlphabetical,
+    Frequency, // Words from the built-in most-common list first, in its order; then the rest
+}
+
+impl ListOrder {
+    fn next(self) -> Self {
+        match self {
+            ListOrder::AsIs => ListOrder::Shuffled,
+            ListOrder::Shuffled => ListOrder::Alphabetical,
+            ListOrder::Alphabetical => ListOrder::ReverseAlphabetical,
+            ListOrder::ReverseAlphabetical => ListOrder::Frequency,
+            ListOrder::Frequency => ListOrder::AsIs,
+        }
+    }
+
+    fn apply(self, words: &[String], rng: &mut impl Rng) -> Vec<String> {
+        let mut ordered = words.to_vec();
+        match self {
+            ListOrder::AsIs => {}
+            ListOrder::Shuffled => ordered.shuffle(rng),
+            ListOrder::Alphabetical => ordered.sort_by_key(|w| w.to_lowercase()),
+            ListOrder::ReverseAlphabetical => ordered.sort_by_key(|w| std::cmp::Reverse(w.to_lowercase())),
+            ListOrder::Frequency => {
+                let rank: HashMap<&str, usize> = DEFAULT_WORDS_STR.split_whitespace().enumerate().map(|(i, w)| (w, i)).collect();
+                // Stable sort: unranked words keep their file order after the ranked ones
+                ordered.sort_by_key(|w| rank.get(w.as_str()).copied().unwrap_or(usize::MAX));
+            }
+        }
+        ordered
+    }
+}
+
+impl PastePolicy {
+    fn next(self) -> Self {
+        match self {
+            PastePolicy::Ignore => PastePolicy::Invalidate,
+            PastePolicy::Invalidate => PastePolicy::Ignore,
+        }
+    }
+}
+
+impl ScrollPolicy {
+    fn next(self) -> Self {
+        match self {
+            ScrollPolicy::Center => ScrollPolicy::Margin,
+            ScrollPolicy::Margin => ScrollPolicy::Center,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)] // Fields missing from older settings.json files take their defaults
 struct Settings {
     forgive_errors: bool,
     default_time_limit: u64,
     default_words_limit: usize,
+    default_chars_limit: usize,
     show_wpm_live: bool,
     auto_save_results: bool,
     min_accuracy_to_save: f64,
+    paste_policy: PastePolicy,
+    show_warmup: bool,
+    completion_animation: bool,
+    completion_sound: bool, // Terminal bell when a test finishes
+    ephemeral: bool, // Keep stats in memory only; settings.json is still written so this sticks
+    show_whitespace: bool, // Draw spaces as a middot in the typing area
+    repeat_until_clean: bool, // Re-queue a word right after itself until typed without errors
+    repeat_max_attempts: u32,
+    // Exponents on each term of the letter weight; 1.0 each is the original formula
+    accuracy_exponent: f64,
+    frequency_exponent: f64,
+    speed_exponent: f64,
+    columns: u8, // 1 or 2 text columns in the typing area
+    last_mode: Option<TestMode>, // Offered as "Repeat Last Test" on the main menu
+    scroll_policy: ScrollPolicy,
+    scroll_margin: usize,
+    min_terminal_size: (u16, u16), // (columns, rows) required at startup
+    backspace_penalty: f64, // Characters taken off the WPM count per backspace (0 = none)
+    blind_mode: bool, // Don't colour typed characters by correctness
+    enter_as_space: bool, // Otherwise Enter is ignored with a footer hint
+    auto_space: bool, // Insert the space after a correctly typed word automatically
+    word_separator: char, // Joins generated words; typed like any other character
+    adaptive_selection: bool, // Off = uniform random words, ignoring letter weights
+    large_text: bool, // Spaced-out, bold text with fewer lines on screen
+    reminder_days: u32, // Nudge on the main menu after this many days without a test (0 = never)
+    theme: Theme,
+    accuracy_floor: f64, // Running accuracy % below which the live readout turns red (0 = off)
+    accuracy_floor_ends_test: bool, // Falling below the floor also ends the run, its result marked as such
+    goal_wpm: f64, // Long-term target shown on the main menu (0 = none)
+    use_alt_screen: bool, // Off = draw the test inline below the prompt, for multiplexers with scrollback trouble
+    enforce_correction: bool, // Wrong keys don't advance and flash the cursor red until the right key is hit
+    word_lists: Vec<String>, // Files mixed into the word list; empty = words.txt or the built-in list
+    progress_format: ProgressFormat,
+    pressure_start_budget: f64, // Seconds for the first word in Pressure mode
+    pressure_decay: f64, // Budget multiplier after each word survived
+    case_slips: bool, // Report wrong-case-only keys ('t' for 'T') separately on the results
+    benchmark_wpm: [f64; 3], // Where each of BENCHMARK_NAMES starts
+    fail_words: bool, // A word with any error earns no WPM credit instead of scaling WPM by accuracy
+    keybindings: KeyBindings,
+    sequential_order: bool, // With adaptive selection off: walk the list in order instead of shuffling
+    list_order: ListOrder, // How the list is arranged for sequential order
+    autosave_secs: u64, // Flush letter stats to disk this often during a test (0 = only at the end)
+    preview_words: bool, // Show a Words test's text first, to accept or reroll before the TUI opens
+    auto_length_ramp: bool, // Raise the minimum word length as recent accuracy climbs
+    count_clean_words: bool, // Words mode ends after N words typed without an error, not N words
+    show_minimap: bool, // Thin strip beside the text: the whole target, with progress and mistakes
+    mode_overrides: HashMap<String, ModeOverrides>, // TestMode::kind() -> settings used for that mode
+    calibration_offered: bool, // The first-run calibration has been suggested once
+    collapse_spaces: bool, // Runs of whitespace inside a word-list entry become a single space
+    number_format: NumberFormat,
+    distractions: bool, // Focus training: a drifting dot and a slowly shifting background during the test
+    pinned_words: Vec<String>, // Vocabulary placed at least once in every generated words text
+    final_space_grace_ms: u64, // Words mode: the last word counts as done this long after its last letter, space or not (0 = space required)
+    show_error_ticker: bool, // Side panel listing the last few expected→typed mistakes during the test
+    results_input_delay_ms: u64, // Keys pressed this soon after the results appear are thrown away
 }
 
 impl Default for Settings {
@@ -121,10 +621,133 @@ impl Default for Settings {
             forgive_errors: false,
             default_time_limit: 60,
             default_words_limit: 25,
+            default_chars_limit: 200,
             show_wpm_live: true,
             auto_save_results: true,
             min_accuracy_to_save: 0.5,
+            paste_policy: PastePolicy::Ignore,
+            show_warmup: false,
+            completion_animation: true,
+            completion_sound: false,
+            ephemeral: false,
+            show_whitespace: false,
+            repeat_until_clean: false,
+            repeat_max_attempts: 3,
+            accuracy_exponent: 1.0,
+            frequency_exponent: 1.0,
+            speed_exponent: 1.0,
+            columns: 1,
+            last_mode: None,
+            scroll_policy: ScrollPolicy::Margin,
+            scroll_margin: 2,
+            min_terminal_size: (60, 20),
+            backspace_penalty: 0.0,
+            blind_mode: false,
+            enter_as_space: true,
+            auto_space: false,
+            word_separator: ' ',
+            adaptive_selection: true,
+            large_text: false,
+            reminder_days: 2,
+            theme: Theme::Violet,
+            accuracy_floor: 0.0,
+            accuracy_floor_ends_test: false,
+            goal_wpm: 0.0,
+            use_alt_screen: true,
+            enforce_correction: false,
+            word_lists: Vec::new(),
+            progress_format: ProgressFormat::Remaining,
+            pressure_start_budget: 5.0,
+            pressure_decay: 0.95,
+            case_slips: false,
+            benchmark_wpm: [30.0, 60.0, 90.0],
+            fail_words: false,
+            keybindings: KeyBindings::default(),
+            sequential_order: false,
+            list_order: ListOrder::AsIs,
+            autosave_secs: 60,
+            preview_words: false,
+            auto_length_ramp: false,
+            count_clean_words: false,
+            show_minimap: false,
+            mode_overrides: HashMap::new(),
+            calibration_offered: false,
+            collapse_spaces: true,
+            number_format: NumberFormat::Auto,
+            distractions: false,
+            pinned_words: Vec::new(),
+            final_space_grace_ms: 400,
+            show_error_ticker: false,
+            results_input_delay_ms: 300,
+        }
+    }
+}
+
+// Per-mode values that replace the global setting for that mode; None = use the global one
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct ModeOverrides {
+    forgive_errors: Option<bool>,
+    enforce_correction: Option<bool>,
+    fail_words: Option<bool>,
+    show_wpm_live: Option<bool>,
+    blind_mode: Option<bool>,
+    auto_space: Option<bool>,
+}
+
+struct OverrideField {
+    label: &'static str,
+    slot: fn(&mut ModeOverrides) -> &mut Option<bool>,
+    global: fn(&mut Settings) -> &mut bool,
+}
+
+const OVERRIDE_FIELDS: [OverrideField; 6] = [
+    OverrideField { label: "Forgive Errors", slot: |o| &mut o.forgive_errors, global: |s| &mut s.forgive_errors },
+    OverrideField { label: "Enforce Correction", slot: |o| &mut o.enforce_correction, global: |s| &mut s.enforce_correction },
+    OverrideField { label: "Failed Word Scoring", slot: |o| &mut o.fail_words, global: |s| &mut s.fail_words },
+    OverrideField { label: "Live WPM", slot: |o| &mut o.show_wpm_live, global: |s| &mut s.show_wpm_live },
+    OverrideField { label: "Blind Mode", slot: |o| &mut o.blind_mode, global: |s| &mut s.blind_mode },
+    OverrideField { label: "Auto Space", slot: |o| &mut o.auto_space, global: |s| &mut s.auto_space },
+];
+
+impl Settings {
+    fn apply_mode_overrides(&mut self, mode: TestMode) {
+        let Some(mut overrides) = self.mode_overrides.get(mode.kind()).cloned() else {
+            return;
+        };
+        for field in &OVERRIDE_FIELDS {
+            if let Some(value) = *(field.slot)(&mut overrides) {
+                *(field.global)(self) = value;
+            }
+        }
+    }
+}
+
+// Final text against the target, position by position. The cursor moves in step with the
+// target, so "extra" is a letter typed where the word had ended and "missed" a separator
+// typed before it did.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+struct CharBreakdown {
+    correct: usize,
+    incorrect: usize,
+    extra: usize,
+    missed: usize,
+}
+
+impl CharBreakdown {
+    fn compare(typed: &str, target: &str, separator: char) -> Self {
+        let mut counts = Self::default();
+        let mut target_chars = target.chars();
+        for t in typed.chars() {
+            match target_chars.next() {
+                Some(expected) if expected == t => counts.correct += 1,
+                Some(expected) if expected == separator => counts.extra += 1,
+                Some(_) if t == separator => counts.missed += 1,
+                Some(_) => counts.incorrect += 1,
+                None => counts.extra += 1, // Past the end of the target
+            }
         }
+        counts
     }
 }
 
@@ -137,9 +760,42 @@ struct TestResult {
     time_taken: f64,
     text_length: usize,
     words_typed: usize,
+    #[serde(default)]
+    word_accuracy: f64, // % of words typed without a single wrong keystroke
+    #[serde(default)]
+    backspaces: usize,
+    #[serde(default)]
+    mode: Option<TestMode>, // None for results saved before modes were recorded
+    #[serde(default)]
+    words_survived: Option<usize>, // Pressure mode score
+    #[serde(default)]
+    best_streak: usize, // Longest run of correct keystrokes in this test
+    #[serde(skip)]
+    streak_record: bool, // best_streak beat the all-time record when the test ended
+    #[serde(default)]
+    failed_words: Option<usize>, // Words that earned no WPM credit, when failed-word scoring is on
+    #[serde(default)]
+    case_slips: Option<usize>, // Errors that were only the wrong case, when that setting is on
+    #[serde(default)]
+    distractions: bool, // Run with distraction mode on
+    #[serde(default)]
+    daily: Option<NaiveDate>, // Set for Daily Challenge runs: the day whose text was typed
+    #[serde(default)]
+    char_breakdown: Option<CharBreakdown>, // None for results saved before it was recorded
+    #[serde(default)]
+    below_floor: Option<f64>, // The accuracy floor (%) that ended the run early
+    #[serde(skip)]
+    invalid_reason: Option<String>, // Set when the run must not be saved
+    #[serde(skip)]
+    typed_text: String, // Kept for the mistakes review, never persisted
+    #[serde(skip)]
+    target_text: String,
+    #[serde(skip)]
+    missed_words: Vec<String>, // Words with any wrong keystroke, for Drill Mistakes
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)] // Like Settings: an older userdata.json missing a map still loads
 struct UserData {
     letter_shown: HashMap<char, u32>,
     letter_correct: HashMap<char, u32>,
@@ -148,457 +804,3616 @@ struct UserData {
     letter_time_count: HashMap<char, u32>,
     letter_wpm: HashMap<char, f64>,
     test_history: Vec<TestResult>,
+    letter_confusions: HashMap<char, HashMap<char, u32>>, // target -> key actually pressed -> count
+    best_streak: usize, // All-time longest run of correct keystrokes in a saved test
+    word_lengths: HashMap<usize, WordLengthStat>, // Word length (capped at MAX_WORD_LENGTH) -> totals
 }
 
-struct AppState {
-    settings: Settings,
-    user_data: UserData,
-    words_list: Vec<String>,
+// Words at least this long share the last bucket
+const MAX_WORD_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+struct WordLengthStat {
+    words: u32,
+    clean: u32, // Typed without a wrong keystroke
+    chars: u32, // Including the separator, which is part of each word's time
+    secs: f64,
 }
 
-impl AppState {
-    fn load() -> Self {
-        let settings = fs::read_to_string("settings.json")
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default();
-
-        let user_data = fs::read_to_string("userdata.json")
-            .ok()
-            .and_then(|s| serde_json::from_str(&s).ok())
-            .unwrap_or_default();
-
-        let words_list = fs::read_to_string("words.txt")
-            .ok()
-            .map(|s| s.lines().map(|l| l.trim().to_string()).collect())
-            .unwrap_or_else(|| {
-                DEFAULT_WORDS_STR
-                    .split_whitespace()
-                    .map(|s| s.to_string())
-                    .collect()
-            });
+// A named set of focus letters, kept in drills.json for reuse
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct SavedDrill {
+    name: String,
+    letters: Vec<char>,
+    words: usize,
+}
 
-        Self {
-            settings,
-            user_data,
-            words_list,
-        }
+// One generated text kept in locked.json and practised again and again, so its runs show
+// skill gained rather than easier or harder texts
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct LockedText {
+    words: Vec<String>, // Joined with the current separator at each run
+    locked_at: DateTime<Local>,
+    runs: Vec<LockedRun>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct LockedRun {
+    timestamp: DateTime<Local>,
+    wpm: f64,
+    accuracy: f64,
+}
+
+// Wrong keys remembered per target letter; the rarest is dropped to make room
+const MAX_CONFUSIONS_PER_LETTER: usize = 8;
+
+const BENCHMARK_NAMES: [&str; 3] = ["Beginner", "Intermediate", "Advanced"];
+
+// "Intermediate (60+ WPM), 8 WPM to Advanced"
+fn benchmark_band(thresholds: &[f64; 3], wpm: f64) -> String {
+    let reached = thresholds.iter().rposition(|&t| wpm >= t);
+    let band = match reached {
+        Some(i) => format!("{} ({}+ WPM)", BENCHMARK_NAMES[i], thresholds[i]),
+        None => format!("Below {} ({} WPM)", BENCHMARK_NAMES[0], thresholds[0]),
+    };
+    let next = reached.map_or(0, |i| i + 1);
+    match thresholds.get(next) {
+        Some(t) => format!("{}, {:.0} WPM to {}", band, t - wpm, BENCHMARK_NAMES[next]),
+        None => band,
     }
+}
 
-    fn save(&self) {
-        if let Ok(json) = serde_json::to_string_pretty(&self.settings) {
-            let _ = fs::write("settings.json", json);
-        }
-        if let Ok(json) = serde_json::to_string_pretty(&self.user_data) {
-            let _ = fs::write("userdata.json", json);
-        }
+// Letters need this many samples before they count as reliably weak
+const MIN_LETTER_SAMPLES: u32 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+struct LetterStat {
+    letter: char,
+    shown: u32,
+    accuracy: f64, // 0.0..=1.0
+    wpm: f64,      // 0.0 when no timing data yet
+}
+
+// --- Aggregate Stats ---
+
+impl UserData {
+    fn total_tests(&self) -> usize {
+        self.test_history.len()
     }
 
-    // Algorithm to select words based on user weakness (High Frequency + Low Accuracy)
-    fn get_weighted_words(&self, count: usize) -> String {
-        let mut rng = thread_rng();
-        
-        // Standard English frequency 
-        let frequency: HashMap<char, f64> = HashMap::from([
-            ('e', 12.02), ('t', 9.10), ('a', 8.12), ('o', 7.68), ('i', 7.31), ('n', 6.95),
-            ('s', 6.28), ('r', 6.02), ('h', 5.92), ('d', 4.32), ('l', 3.98), ('u', 2.88),
-            ('c', 2.71), ('m', 2.61), ('f', 2.30), ('y', 2.11), ('w', 2.09), ('g', 2.03),
-            ('p', 1.82), ('b', 1.49), ('v', 1.11), ('k', 0.69), ('x', 0.17), ('q', 0.11),
-            ('j', 0.10), ('z', 0.07),
-        ]);
+    fn average_wpm(&self) -> Option<f64> {
+        self.average(|r| r.wpm)
+    }
 
-        let mut letter_weight = HashMap::new();
-        for ch in ' '..='~' {
-            let acc = *self.user_data.letter_accuracy.get(&ch).unwrap_or(&0.0);
-            let wpm = *self.user_data.letter_wpm.get(&ch).unwrap_or(&0.0);
-            
-            // If accuracy is high, weight is low. If accuracy is low, weight is high.
-            let inv_acc = if acc > 0.01 { 1.0 / acc } else { 20.0 };
-            let wpm_weight = 1.0 / (wpm + 0.1);
+    fn average_accuracy(&self) -> Option<f64> {
+        self.average(|r| r.accuracy)
+    }
 
-            if let Some(freq) = frequency.get(&ch) {
-                letter_weight.insert(ch, inv_acc * freq * wpm_weight);
-            } else {
-                letter_weight.insert(ch, 1.0);
-            }
-        }
+    fn best_result(&self) -> Option<&TestResult> {
+        self.test_history.iter().max_by(|a, b| a.wpm.total_cmp(&b.wpm))
+    }
 
-        let mut word_weights = Vec::with_capacity(self.words_list.len());
-        for word in &self.words_list {
-            let mut weight = 0.0;
-            let mut len = 0.0;
-            for ch in word.chars() {
-                let w = letter_weight.get(&ch).unwrap_or(&1.0);
-                weight += w;
-                len += 1.0;
-            }
-            if len > 0.0 {
-                word_weights.push(weight / len);
-            } else {
-                word_weights.push(0.0);
-            }
-        }
+    fn best_wpm(&self) -> Option<f64> {
+        self.best_result().map(|r| r.wpm)
+    }
 
-        let mut chosen_words = Vec::new();
-        if let Ok(dist) = rand::distributions::WeightedIndex::new(&word_weights) {
-            for _ in 0..count {
-                chosen_words.push(self.words_list[dist.sample(&mut rng)].clone());
-            }
-        } else {
-            // Fallback
-            for _ in 0..count {
-                chosen_words.push(self.words_list.choose(&mut rng).unwrap().clone());
-            }
+    // Consecutive days with a saved Daily Challenge, ending today (or yesterday, if today's is still to do)
+    fn daily_streak(&self, today: NaiveDate) -> usize {
+        let days: HashSet<NaiveDate> = self.test_history.iter().filter_map(|r| r.daily).collect();
+        let mut day = if days.contains(&today) { today } else { today - chrono::Duration::days(1) };
+        let mut streak = 0;
+        while days.contains(&day) {
+            streak += 1;
+            day -= chrono::Duration::days(1);
         }
+        streak
+    }
 
-        chosen_words.join(" ")
+    // Lowest accuracy among the last `n` saved tests (the earlier one wins ties)
+    fn worst_recent(&self, n: usize) -> Option<&TestResult> {
+        self.test_history[self.test_history.len().saturating_sub(n)..]
+            .iter()
+            .reduce(|worst, r| if r.accuracy < worst.accuracy { r } else { worst })
     }
 
-    fn update_stats(&mut self, char: char, is_correct: bool, time_taken: f64) {
-        let shown = self.user_data.letter_shown.entry(char).or_insert(0);
-        *shown += 1;
-        
-        if is_correct {
-            *self.user_data.letter_correct.entry(char).or_insert(0) += 1;
-            *self.user_data.letter_time_total.entry(char).or_insert(0.0) += time_taken;
-            *self.user_data.letter_time_count.entry(char).or_insert(0) += 1;
+    // Mean accuracy of the last `n` saved tests
+    fn recent_accuracy(&self, n: usize) -> Option<f64> {
+        let recent = &self.test_history[self.test_history.len().saturating_sub(n)..];
+        if recent.is_empty() {
+            return None;
         }
+        Some(recent.iter().map(|r| r.accuracy).sum::<f64>() / recent.len() as f64)
+    }
 
-        let s = *self.user_data.letter_shown.get(&char).unwrap_or(&0) as f64;
-        let c = *self.user_data.letter_correct.get(&char).unwrap_or(&0) as f64;
-        
-        if s > 0.0 {
-            self.user_data.letter_accuracy.insert(char, c / s);
+    fn average(&self, field: impl Fn(&TestResult) -> f64) -> Option<f64> {
+        if self.test_history.is_empty() {
+            return None;
         }
+        Some(self.test_history.iter().map(field).sum::<f64>() / self.test_history.len() as f64)
+    }
 
-        let total_time = *self.user_data.letter_time_total.get(&char).unwrap_or(&0.0);
-        let count = *self.user_data.letter_time_count.get(&char).unwrap_or(&0);
-        if count > 0 && total_time > 0.0 {
-             let avg = total_time / count as f64;
-             self.user_data.letter_wpm.insert(char, 12.0 / avg);
-        }
+    // Every letter seen so far, alphabetical
+    fn letter_stats(&self) -> Vec<LetterStat> {
+        let mut stats: Vec<LetterStat> = self
+            .letter_shown
+            .iter()
+            .map(|(&letter, &shown)| LetterStat {
+                letter,
+                shown,
+                accuracy: *self.letter_accuracy.get(&letter).unwrap_or(&0.0),
+                wpm: *self.letter_wpm.get(&letter).unwrap_or(&0.0),
+            })
+            .collect();
+        stats.sort_by_key(|s| s.letter);
+        stats
     }
-}
 
-// --- TUI Game Loop ---
+    // (target, pressed, count), most frequent first
+    fn top_confusions(&self, n: usize) -> Vec<(char, char, u32)> {
+        let mut all: Vec<(char, char, u32)> = self
+            .letter_confusions
+            .iter()
+            .flat_map(|(&target, pressed)| pressed.iter().map(move |(&key, &count)| (target, key, count)))
+            .collect();
+        all.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+        all.truncate(n);
+        all
+    }
 
-#[derive(PartialEq)]
-enum TestMode {
-    Time(u64),
-    Words(usize),
-    Forever,
+    // (hour, average WPM, tests) for each local hour of the day that has any tests
+    fn wpm_by_hour(&self) -> Vec<(u32, f64, usize)> {
+        let mut buckets = [(0.0, 0); 24];
+        for res in &self.test_history {
+            let bucket = &mut buckets[res.timestamp.hour() as usize];
+            bucket.0 += res.wpm;
+            bucket.1 += 1;
+        }
+        (0..24u32)
+            .zip(buckets)
+            .filter(|(_, (_, n))| *n > 0)
+            .map(|(hour, (sum, n))| (hour, sum / n as f64, n))
+            .collect()
+    }
+
+    // Lowest accuracy first (slower wins ties), ignoring letters with too few samples and spaces
+    fn weakest_letters(&self, n: usize) -> Vec<LetterStat> {
+        let mut stats: Vec<LetterStat> = self
+            .letter_stats()
+            .into_iter()
+            .filter(|s| s.shown >= MIN_LETTER_SAMPLES && !s.letter.is_whitespace())
+            .collect();
+        stats.sort_by(|a, b| a.accuracy.total_cmp(&b.accuracy).then(a.wpm.total_cmp(&b.wpm)));
+        stats.truncate(n);
+        stats
+    }
 }
 
-fn run_test(app: &mut AppState, mode: TestMode) -> Result<Option<TestResult>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+struct AppState {
+    settings: Settings,
+    user_data: UserData,
+    words_list: Vec<String>,
+    load_errors: Vec<String>, // Problems hit while loading, shown once the menu is up
+    ephemeral_session: bool, // --ephemeral: nothing is read from or written to disk
+    letter_set: Option<Vec<char>>, // Custom Letters drill: generate pseudo-words from these only
+    focus_letters: Vec<char>, // --focus / Focus Letters / Practice Weakest: words with these letters are strongly preferred
+    in_order: Option<usize>, // Common Words drill: next index into the word list, cycled in order
+    sequential_cursor: usize, // Next word for sequential (non-adaptive) order, kept for the session
+    ordered_list: Option<Vec<String>>, // words_list in `list_order`, built on first use; None = rebuild
+    drill_words: Option<Vec<String>>, // Drill Mistakes: the missed words of the previous test
+    prepared_text: Option<String>, // Accepted in the Words preview, used by the next test instead of generating
+    session_forgive: Option<bool>, // Mid-test toggle of forgive_errors; cleared when Settings is opened
+    exam: bool, // Exam Mode run: wrong keys are never blocked and the toggle is ignored
+    drills: Vec<SavedDrill>,
+    locked_text: Option<LockedText>,
+    protected_files: Vec<&'static str>, // Existed but couldn't be loaded: never overwritten this session
+    session_letters: HashMap<char, (u32, u32)>, // (shown, correct) for the running test only
+    rng: StdRng, // Shared by all text generation so --seed makes it reproducible
+    unsaved_stats: bool, // Letter stats changed since the last save
+    interrupted: Arc<AtomicBool>, // Set by SIGINT (or Ctrl+C during a test)
+}
+
+// Missing files fall back to defaults quietly. A file that exists but can't be read or
+// parsed (bad UTF-8, a half-written save) is reported and gives None, so the caller can
+// keep it from being overwritten.
+fn read_json<T: DeserializeOwned + Default>(path: &str, errors: &mut Vec<String>) -> Option<T> {
+    match fs::read_to_string(path) {
+        Ok(s) => match serde_json::from_str(&s) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                errors.push(format!("Could not parse {}: {}. It won't be overwritten this session.", path, e));
+                None
+            }
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => Some(T::default()),
+        Err(e) => {
+            errors.push(format!("Could not read {}: {}. It won't be overwritten this session.", path, e));
+            None
+        }
+    }
+}
+
+// Defaults for a file that couldn't be loaded, which is then added to `protected`
+fn read_or_protect<T: DeserializeOwned + Default>(path: &'static str, errors: &mut Vec<String>, protected: &mut Vec<&'static str>) -> T {
+    read_json(path, errors).unwrap_or_else(|| {
+        protected.push(path);
+        T::default()
+    })
+}
+
+fn save_or_warn(app: &AppState) -> bool {
+    match app.save() {
+        Ok(()) => true,
+        Err(e) => {
+            gum_error(&format!("Save failed: {:#}", e));
+            false
+        }
+    }
+}
+
+// Unique, sorted letters from user input, whitespace ignored
+fn parse_letters(input: &str) -> Vec<char> {
+    let mut letters: Vec<char> = input.nfc().filter(|c| !c.is_whitespace()).collect();
+    letters.sort_unstable();
+    letters.dedup();
+    letters
+}
+
+// Random "pronounceable-ish" words built only from `letters`: vowels and consonants
+// alternate when the set has both, otherwise letters are picked freely.
+fn pseudo_words(letters: &[char], count: usize, separator: char, rng: &mut impl Rng) -> String {
+    let (vowels, consonants): (Vec<char>, Vec<char>) =
+        letters.iter().partition(|c| "aeiouy".contains(c.to_ascii_lowercase()));
+
+    let mut words = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len = rng.gen_range(2..=6);
+        let mut use_vowel = rng.gen_bool(0.3);
+        let mut word = String::with_capacity(len);
+        for _ in 0..len {
+            let pool = match (use_vowel, vowels.is_empty(), consonants.is_empty()) {
+                (true, false, _) | (false, _, true) => &vowels,
+                _ => &consonants,
+            };
+            if let Some(c) = pool.choose(rng) {
+                word.push(*c);
+            }
+            use_vowel = !use_vowel;
+        }
+        words.push(word);
+    }
+    words.join(&separator.to_string())
+}
+
+// One word-list line as a target: trimmed, NFC so a precomposed "é" is one character
+// whatever the file used, and with inner runs of spaces squeezed when `collapse` is set
+fn clean_entry(line: &str, collapse: bool) -> String {
+    let line = line.trim();
+    if collapse {
+        line.split_whitespace().collect::<Vec<_>>().join(" ").nfc().collect()
+    } else {
+        line.nfc().collect()
+    }
+}
+
+// Pinned words not already picked overwrite random slots, so the count stays the same;
+// with more pins than slots only as many as fit make it in
+fn pin_words<'a>(chosen: &mut [&'a str], pinned: &'a [String], rng: &mut impl Rng) {
+    let missing: Vec<&str> = pinned.iter().map(|w| w.as_str()).filter(|w| !w.is_empty() && !chosen.contains(w)).collect();
+    if missing.is_empty() {
+        return;
+    }
+    let slots = rand::seq::index::sample(rng, chosen.len(), missing.len().min(chosen.len()));
+    for (slot, word) in slots.into_iter().zip(missing) {
+        chosen[slot] = word;
+    }
+}
+
+// Monkeytype's list format: {"name": "english", "words": ["the", "be", ...]}. Non-string
+// entries are skipped; a list with no words at all is an error.
+fn parse_monkeytype(text: &str) -> Result<Vec<String>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| format!("not valid JSON ({})", e))?;
+    let words = value.get("words").ok_or("no \"words\" field")?.as_array().ok_or("\"words\" isn't a list")?;
+    let words: Vec<String> = words.iter().filter_map(|w| w.as_str()).map(|w| w.to_string()).collect();
+    if words.is_empty() {
+        return Err("\"words\" has no text entries".to_string());
+    }
+    Ok(words)
+}
+
+// Selected lists concatenated, keeping the first copy of words that appear in several
+fn load_words(lists: &[String], collapse_spaces: bool, errors: &mut Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    for path in lists {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let entries: Vec<String> = if path.ends_with(".json") {
+                    match parse_monkeytype(&text) {
+                        Ok(entries) => entries,
+                        Err(e) => {
+                            errors.push(format!("Skipped word list {}: {}", path, e));
+                            continue;
+                        }
+                    }
+                } else {
+                    text.lines().map(|l| l.to_string()).collect()
+                };
+                for word in entries.iter().map(|l| clean_entry(l, collapse_spaces)).filter(|w| !w.is_empty()) {
+                    if seen.insert(word.clone()) {
+                        words.push(word);
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("Couldn't read word list {}: {}", path, e)),
+        }
+    }
+    if !words.is_empty() {
+        return words;
+    }
+
+    match fs::read_to_string("words.txt") {
+        Ok(s) => s.lines().map(|l| clean_entry(l, collapse_spaces)).collect(),
+        Err(e) => {
+            // Missing is normal; unreadable (bad UTF-8, permissions) is worth knowing about
+            if e.kind() != ErrorKind::NotFound {
+                errors.push(format!("Couldn't read words.txt: {}. Using the built-in list.", e));
+            }
+            DEFAULT_WORDS_STR
+                .split_whitespace()
+                .map(|s| s.to_string())
+                .collect()
+        }
+    }
+}
+
+// .txt files in the working directory and in wordlists/, candidates for mixing
+fn find_word_lists() -> Vec<String> {
+    // JSON only from wordlists/: the data directory's own .json files aren't word lists
+    let mut found: Vec<String> = [(".", false), ("wordlists", true)]
+        .iter()
+        .filter_map(|&(dir, json)| fs::read_dir(dir).ok().map(|entries| (entries, json)))
+        .flat_map(|(entries, json)| entries.filter_map(|entry| entry.ok()).map(move |entry| (entry.path(), json)))
+        .filter(|(path, json)| path.extension().is_some_and(|ext| ext == "txt" || (*json && ext == "json")))
+        .map(|(path, _)| path)
+        .map(|path| path.strip_prefix("./").unwrap_or(&path).display().to_string())
+        .collect();
+    found.sort();
+    found
+}
+
+impl AppState {
+    fn load(ephemeral_session: bool) -> Self {
+        let mut load_errors = color_override_errors();
+        let mut protected_files = Vec::new();
+        let mut settings: Settings = if ephemeral_session {
+            Settings::default()
+        } else if !std::path::Path::new("settings.json").exists() {
+            // First run: write the defaults so there's a file to discover and edit
+            let settings = Settings::default();
+            if let Err(e) = serde_json::to_string_pretty(&settings).map_err(io::Error::from).and_then(|json| fs::write("settings.json", json)) {
+                load_errors.push(format!("Could not write default settings.json: {}", e));
+            }
+            settings
+        } else {
+            read_or_protect("settings.json", &mut load_errors, &mut protected_files)
+        };
+        if let Err(e) = settings.keybindings.resolve() {
+            load_errors.push(format!("Key bindings: {}. Using the defaults.", e));
+            settings.keybindings = KeyBindings::default();
+        }
+        let user_data = if ephemeral_session || settings.ephemeral {
+            UserData::default()
+        } else {
+            read_or_protect("userdata.json", &mut load_errors, &mut protected_files)
+        };
+
+        let drills = if ephemeral_session { Vec::new() } else { read_or_protect("drills.json", &mut load_errors, &mut protected_files) };
+        let locked_text = if ephemeral_session { None } else { read_or_protect("locked.json", &mut load_errors, &mut protected_files) };
+
+        let words_list = load_words(&settings.word_lists, settings.collapse_spaces, &mut load_errors);
+
+        Self {
+            settings,
+            user_data,
+            words_list,
+            load_errors,
+            ephemeral_session,
+            letter_set: None,
+            focus_letters: Vec::new(),
+            in_order: None,
+            sequential_cursor: 0,
+            ordered_list: None,
+            drill_words: None,
+            prepared_text: None,
+            session_forgive: None,
+            exam: false,
+            drills,
+            locked_text,
+            protected_files,
+            session_letters: HashMap::new(),
+            rng: StdRng::from_entropy(),
+            unsaved_stats: false,
+            interrupted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn forgive_errors(&self) -> bool {
+        !self.exam && self.session_forgive.unwrap_or(self.settings.forgive_errors)
+    }
+
+    fn is_ephemeral(&self) -> bool {
+        self.ephemeral_session || self.settings.ephemeral
+    }
+
+    // After the word list settings change; returns the problems hit while loading
+    fn reload_words(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.words_list = load_words(&self.settings.word_lists, self.settings.collapse_spaces, &mut errors);
+        self.ordered_list = None;
+        self.sequential_cursor = 0;
+        errors
+    }
+
+    fn can_write(&self, path: &str) -> bool {
+        !self.ephemeral_session && !self.protected_files.contains(&path)
+    }
+
+    fn save(&self) -> Result<()> {
+        let res = self.write_files();
+        match &res {
+            Ok(()) => log_event!("saved ({} tests in history)", self.user_data.test_history.len()),
+            Err(e) => log_event!("save failed: {:#}", e),
+        }
+        res
+    }
+
+    fn write_files(&self) -> Result<()> {
+        if self.can_write("settings.json") {
+            let json = serde_json::to_string_pretty(&self.settings)?;
+            fs::write("settings.json", json).context("Failed to write settings.json")?;
+        }
+        if self.settings.ephemeral || !self.can_write("userdata.json") {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&self.user_data)?;
+        fs::write("userdata.json", json).context("Failed to write userdata.json")?;
+        Ok(())
+    }
+
+    // Only written when a drill is added or removed, not with every save
+    fn save_drills(&self) -> Result<()> {
+        if !self.can_write("drills.json") {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&self.drills)?;
+        fs::write("drills.json", json).context("Failed to write drills.json")?;
+        Ok(())
+    }
+
+    // Unlocking removes the file rather than leaving a `null` behind
+    fn save_locked(&self) -> Result<()> {
+        if !self.can_write("locked.json") {
+            return Ok(());
+        }
+        match &self.locked_text {
+            Some(locked) => {
+                let json = serde_json::to_string_pretty(locked)?;
+                fs::write("locked.json", json).context("Failed to write locked.json")?;
+            }
+            None => match fs::remove_file("locked.json") {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e).context("Failed to remove locked.json"),
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    // Appends to history when the result meets the save rules, and flushes letter stats
+    // either way. Errors are save failures.
+    fn record_result(&mut self, res: &TestResult) -> Result<()> {
+        let keep = res.invalid_reason.is_none()
+            && self.settings.auto_save_results && res.accuracy >= self.settings.min_accuracy_to_save * 100.0;
+        if keep {
+            self.user_data.best_streak = self.user_data.best_streak.max(res.best_streak);
+            self.user_data.test_history.push(res.clone());
+        }
+        if keep || self.unsaved_stats {
+            self.save()?;
+            self.unsaved_stats = false;
+        }
+        Ok(())
+    }
+
+    // Text for a test, from whichever source the current session uses
+    fn generate_text(&mut self, count: usize) -> String {
+        let text = self.pick_text(count);
+        log_event!("generated {} words: {:?}", count, text);
+        text
+    }
+
+    fn pick_text(&mut self, count: usize) -> String {
+        if let Some(words) = &self.drill_words {
+            let chosen: Vec<&str> = (0..count).filter_map(|_| words.choose(&mut self.rng).map(|w| w.as_str())).collect();
+            return chosen.join(&self.settings.word_separator.to_string());
+        }
+        if let Some(start) = self.in_order {
+            // Most common first whatever order the list is in, so the loop drills the words that matter most
+            let by_frequency = ListOrder::Frequency.apply(&self.words_list, &mut self.rng);
+            let (text, next) = self.words_in_order(&by_frequency, start, count, COMMON_WORDS);
+            self.in_order = Some(next);
+            return text;
+        }
+        match &self.letter_set {
+            Some(letters) => pseudo_words(letters, count, self.settings.word_separator, &mut self.rng),
+            None => self.get_weighted_words(count),
+        }
+    }
+
+    // `count` words from the first `limit` entries of the list, in order from `start` and
+    // looping; also returns where the next call should start.
+    fn words_in_order(&self, list: &[String], start: usize, count: usize, limit: usize) -> (String, usize) {
+        let pool = list.len().min(limit);
+        if pool == 0 {
+            return (String::new(), 0);
+        }
+        let words: Vec<&str> = (start..start + count).map(|i| list[i % pool].as_str()).collect();
+        (words.join(&self.settings.word_separator.to_string()), (start + count) % pool)
+    }
+
+    // Per-letter weakness score: high frequency + low accuracy + low speed = high weight
+    fn letter_weights(&self) -> HashMap<char, f64> {
+        // Standard English frequency 
+        #[allow(clippy::approx_constant)]
+        let frequency: HashMap<char, f64> = HashMap::from([
+            ('e', 12.02), ('t', 9.10), ('a', 8.12), ('o', 7.68), ('i', 7.31), ('n', 6.95),
+            ('s', 6.28), ('r', 6.02), ('h', 5.92), ('d', 4.32), ('l', 3.98), ('u', 2.88),
+            ('c', 2.71), ('m', 2.61), ('f', 2.30), ('y', 2.11), ('w', 2.09), ('g', 2.03),
+            ('p', 1.82), ('b', 1.49), ('v', 1.11), ('k', 0.69), ('x', 0.17), ('q', 0.11),
+            ('j', 0.10), ('z', 0.07),
+        ]);
+
+        // Overall accuracy, the value a letter with little data is pulled towards
+        let total_shown: u32 = self.user_data.letter_shown.values().sum();
+        let total_correct: u32 = self.user_data.letter_correct.values().sum();
+        let mean_acc = if total_shown > 0 { total_correct as f64 / total_shown as f64 } else { 1.0 };
+
+        let mut letter_weight = HashMap::new();
+        for ch in ' '..='~' {
+            // Laplace-style smoothing: one lucky miss can't make a letter look hopeless
+            let shown = *self.user_data.letter_shown.get(&ch).unwrap_or(&0) as f64;
+            let correct = *self.user_data.letter_correct.get(&ch).unwrap_or(&0) as f64;
+            let acc = (correct + ACCURACY_PRIOR * mean_acc) / (shown + ACCURACY_PRIOR);
+            let wpm = *self.user_data.letter_wpm.get(&ch).unwrap_or(&0.0);
+            
+            // If accuracy is high, weight is low. If accuracy is low, weight is high.
+            let inv_acc = if acc > 0.01 { 1.0 / acc } else { 20.0 };
+            let wpm_weight = 1.0 / (wpm + 0.1);
+
+            if let Some(freq) = frequency.get(&ch) {
+                let weight = inv_acc.powf(self.settings.accuracy_exponent)
+                    * freq.powf(self.settings.frequency_exponent)
+                    * wpm_weight.powf(self.settings.speed_exponent);
+                letter_weight.insert(ch, weight);
+            } else {
+                letter_weight.insert(ch, 1.0);
+            }
+        }
+
+        // Misses in the running test boost a letter straight away (3 misses in 3 tries = 3.4x)
+        for (ch, &(shown, correct)) in &self.session_letters {
+            let misses = shown.saturating_sub(correct) as f64;
+            if let Some(w) = letter_weight.get_mut(ch) {
+                *w *= 1.0 + 4.0 * misses / (shown as f64 + 2.0);
+            }
+        }
+        for ch in &self.focus_letters {
+            if let Some(w) = letter_weight.get_mut(ch) {
+                *w *= FOCUS_BOOST;
+            }
+        }
+        letter_weight
+    }
+
+    // How hard `text` is for this user compared to the word list as a whole (1.0 = typical)
+    fn difficulty_ratio(&self, text: &str) -> f64 {
+        let letter_weight = self.letter_weights();
+        let mean_weight = |chars: &mut dyn Iterator<Item = char>| {
+            let (sum, n) = chars
+                .filter(|c| !c.is_whitespace())
+                .fold((0.0, 0usize), |(sum, n), c| (sum + letter_weight.get(&c).unwrap_or(&1.0), n + 1));
+            if n > 0 { sum / n as f64 } else { 0.0 }
+        };
+
+        let baseline = mean_weight(&mut self.words_list.iter().flat_map(|w| w.chars()));
+        if baseline > 0.0 {
+            mean_weight(&mut text.chars()) / baseline
+        } else {
+            1.0
+        }
+    }
+
+    // Algorithm to select words based on user weakness (High Frequency + Low Accuracy)
+    fn get_weighted_words(&mut self, count: usize) -> String {
+        let separator = self.settings.word_separator.to_string();
+        // Blank lines from a word list are never picked; with nothing usable left the
+        // built-in list stands in rather than producing an empty test
+        let mut pool: Vec<&str> = self.words_list.iter().map(|w| w.as_str()).filter(|w| !w.is_empty()).collect();
+        if pool.is_empty() {
+            pool = DEFAULT_WORDS_STR.split_whitespace().collect();
+        }
+
+        if !self.settings.adaptive_selection && self.settings.sequential_order {
+            // Carries on where the last test (or refill) stopped
+            if self.ordered_list.is_none() {
+                self.ordered_list = Some(self.settings.list_order.apply(&self.words_list, &mut self.rng));
+            }
+            let list = self.ordered_list.as_deref().unwrap_or_default();
+            let (text, next) = self.words_in_order(list, self.sequential_cursor, count, usize::MAX);
+            self.sequential_cursor = next;
+            return text;
+        }
+        let min_len = self.ramp_min_length();
+        let longest = pool.iter().map(|w| w.chars().count()).max().unwrap_or(1);
+        let min_len = min_len.min(longest);
+        pool.retain(|w| w.chars().count() >= min_len);
+
+        if !self.settings.adaptive_selection {
+            // Uniform picks, so results stay comparable across sessions
+            let mut chosen: Vec<&str> = (0..count).filter_map(|_| pool.choose(&mut self.rng).copied()).collect();
+            pin_words(&mut chosen, &self.settings.pinned_words, &mut self.rng);
+            return chosen.join(&separator);
+        }
+
+        let letter_weight = self.letter_weights();
+        if LOG.get().is_some() {
+            let mut heaviest: Vec<(&char, &f64)> = letter_weight.iter().filter(|(c, _)| c.is_alphabetic()).collect();
+            heaviest.sort_by(|a, b| b.1.total_cmp(a.1));
+            let top: Vec<String> = heaviest.iter().take(5).map(|(c, w)| format!("{}={:.2}", c, w)).collect();
+            log_event!("weights: pool {} words (min length {}), heaviest {}", pool.len(), min_len, top.join(" "));
+        }
+
+        let word_weights: Vec<f64> = pool
+            .iter()
+            .map(|word| {
+                let total: f64 = word.chars().map(|ch| letter_weight.get(&ch).unwrap_or(&1.0)).sum();
+                let weight = total / word.chars().count() as f64;
+                // Extreme exponents can overflow or zero out; every word keeps some chance
+                if weight.is_finite() { weight.max(MIN_WORD_WEIGHT) } else { MIN_WORD_WEIGHT }
+            })
+            .collect();
+
+        let mut chosen_words: Vec<&str> = match rand::distributions::WeightedIndex::new(&word_weights) {
+            Ok(dist) => (0..count).map(|_| pool[dist.sample(&mut self.rng)]).collect(),
+            // Fallback
+            Err(_) => (0..count).filter_map(|_| pool.choose(&mut self.rng).copied()).collect(),
+        };
+        pin_words(&mut chosen_words, &self.settings.pinned_words, &mut self.rng);
+
+        chosen_words.join(&separator)
+    }
+
+    // Length ramp: 1 below RAMP_START_ACCURACY over the last RAMP_TESTS, rising to
+    // RAMP_MAX_LENGTH at 100%. Callers clamp it to the longest word available.
+    fn ramp_min_length(&self) -> usize {
+        if !self.settings.auto_length_ramp {
+            return 1;
+        }
+        let Some(accuracy) = self.user_data.recent_accuracy(RAMP_TESTS) else {
+            return 1;
+        };
+        let progress = ((accuracy - RAMP_START_ACCURACY) / (100.0 - RAMP_START_ACCURACY)).clamp(0.0, 1.0);
+        1 + (progress * (RAMP_MAX_LENGTH - 1) as f64).floor() as usize
+    }
+
+    // `time_taken` is None when there is no meaningful delta (the first keystroke of a test)
+    // Each screen position counts once: a `retry` after a blocked miss (forgive errors,
+    // enforced correction) already counted as shown and missed, so it only adds confusions
+    fn update_stats(&mut self, char: char, typed: char, time_taken: Option<f64>, retry: bool) {
+        self.unsaved_stats = true;
+        let is_correct = typed == char;
+        if !retry {
+            *self.user_data.letter_shown.entry(char).or_insert(0) += 1;
+        }
+
+        if is_correct && retry {
+            // Credit went with the first attempt
+        } else if is_correct {
+            *self.user_data.letter_correct.entry(char).or_insert(0) += 1;
+            if let Some(time_taken) = time_taken {
+                let total = self.user_data.letter_time_total.entry(char).or_insert(0.0);
+                let count = self.user_data.letter_time_count.entry(char).or_insert(0);
+                *total += time_taken;
+                *count += 1;
+                // Halve the samples (keeping the average) so new keystrokes keep moving it
+                if *count > MAX_TIME_SAMPLES {
+                    let halved = *count / 2;
+                    *total *= halved as f64 / *count as f64;
+                    *count = halved;
+                }
+            }
+        } else {
+            let confusions = self.user_data.letter_confusions.entry(char).or_default();
+            if !confusions.contains_key(&typed) && confusions.len() >= MAX_CONFUSIONS_PER_LETTER {
+                if let Some(rarest) = confusions.iter().min_by_key(|(_, &n)| n).map(|(&c, _)| c) {
+                    confusions.remove(&rarest);
+                }
+            }
+            *confusions.entry(typed).or_insert(0) += 1;
+        }
+
+        let s = *self.user_data.letter_shown.get(&char).unwrap_or(&0) as f64;
+        let c = *self.user_data.letter_correct.get(&char).unwrap_or(&0) as f64;
+        
+        if s > 0.0 {
+            self.user_data.letter_accuracy.insert(char, c / s);
+        }
+
+        let total_time = *self.user_data.letter_time_total.get(&char).unwrap_or(&0.0);
+        let count = *self.user_data.letter_time_count.get(&char).unwrap_or(&0);
+        if count > 0 && total_time > 0.0 {
+             let avg = total_time / count as f64;
+             self.user_data.letter_wpm.insert(char, 12.0 / avg);
+        }
+    }
+
+    // A finished word, timed from the end of the previous one
+    fn record_word(&mut self, len: usize, clean: bool, secs: f64) {
+        if len == 0 {
+            return;
+        }
+        self.unsaved_stats = true;
+        let stat = self.user_data.word_lengths.entry(len.min(MAX_WORD_LENGTH)).or_default();
+        stat.words += 1;
+        stat.clean += clean as u32;
+        stat.chars += len as u32 + 1;
+        stat.secs += secs;
+    }
+}
+
+// --- TUI Game Loop ---
+
+// Live error ticker: how many mistakes it lists, and its width in cells
+const ERROR_TICKER_LEN: usize = 8;
+const ERROR_TICKER_WIDTH: u16 = 7;
+
+// Whitespace gets a visible stand-in, or "e→ " would look like nothing was typed
+fn ticker_char(c: char) -> String {
+    match c {
+        ' ' => "␣".to_string(),
+        '\n' => "⏎".to_string(),
+        '\t' => "⇥".to_string(),
+        c => c.to_string(),
+    }
+}
+
+// Enter types the separator (always, when the separator is a newline); otherwise it stays
+// Enter, which only shows a hint
+fn enter_key(code: KeyCode, enter_as_space: bool, sep: char) -> KeyCode {
+    match code {
+        KeyCode::Enter if enter_as_space || sep == '\n' => KeyCode::Char(sep),
+        code => code,
+    }
+}
+
+fn difficulty_label(ratio: f64) -> &'static str {
+    if ratio < 0.9 {
+        "Easy"
+    } else if ratio < 1.15 {
+        "Medium"
+    } else {
+        "Hard"
+    }
+}
+
+// Colour of a target character given what was typed at its position (None = not reached yet)
+fn char_style(expected: char, typed: Option<char>, is_cursor: bool) -> Style {
+    match typed {
+        Some(t) if t == expected => Style::default().fg(themed("CORRECT", Color::Green)),
+        Some(_) => Style::default().fg(themed("INCORRECT", Color::Red)).add_modifier(Modifier::UNDERLINED),
+        None if is_cursor => Style::default().fg(themed("CURSOR", Color::Blue)).add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+        None => Style::default().fg(themed("UPCOMING", Color::Gray)),
+    }
+}
+
+const WARMUP_SAMPLES: usize = 40; // Keystrokes considered for the warm-up gauge
+const PRESSURE_TICK: Duration = Duration::from_millis(100); // Redraw rate for the per-word countdown
+const PRESSURE_MIN_BUDGET: f64 = 0.5; // Seconds; the budget never shrinks below this
+const CLOCK_TICK: Duration = Duration::from_millis(500); // Idle redraw interval, keeps the clock and WPM moving
+const FLOOR_MIN_KEYSTROKES: u32 = 20; // Don't judge the accuracy floor on the first few keys
+const ACCURACY_PRIOR: f64 = 5.0; // Pseudo-samples at the mean accuracy added to every letter
+const MAX_TIME_SAMPLES: u32 = 1000; // Per-letter timing samples before they're rescaled
+const FOCUS_BOOST: f64 = 50.0; // Weight multiplier for Practice Weakest letters
+const COMMON_WORDS: usize = 100; // Size of the Common Words loop
+const MIN_WORD_WEIGHT: f64 = 1e-6; // Floor for a word's selection weight
+const RAMP_TESTS: usize = 10; // Recent tests whose accuracy drives the length ramp
+const RAMP_START_ACCURACY: f64 = 90.0;
+const RAMP_MAX_LENGTH: usize = 7;
+const REVIEW_RECENT: usize = 10; // Runs Review Worst Run picks from
+const DAILY_WORDS: usize = 30; // Length of the Daily Challenge
+const DRILL_REPEATS: usize = 3; // Times each missed word comes up in Drill Mistakes
+const PRACTICE_WORDS: usize = 20; // Length of a Practice Weakest drill
+const PRACTICE_LETTERS: usize = 3; // How many weak letters a drill targets
+
+// 0.0 = erratic, 1.0 = steady rhythm. Uses the coefficient of variation of the latest deltas.
+fn warmup_level(deltas: &[f64]) -> f64 {
+    let recent = &deltas[deltas.len().saturating_sub(10)..];
+    if recent.len() < 3 {
+        return 0.0;
+    }
+    let mean = recent.iter().sum::<f64>() / recent.len() as f64;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    let variance = recent.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / recent.len() as f64;
+    (1.0 - variance.sqrt() / mean).clamp(0.0, 1.0)
+}
+
+// True when `typed` is the start of a precomposed `target` ("e" or "ẹ" for "ệ"), so a
+// combining mark may still follow
+fn awaits_mark(typed: char, target: Option<char>) -> bool {
+    target.is_some_and(|t| {
+        let parts: Vec<char> = std::iter::once(t).nfd().collect();
+        let typed_parts: Vec<char> = std::iter::once(typed).nfd().collect();
+        typed_parts.len() < parts.len() && parts.starts_with(&typed_parts)
+    })
+}
+
+// The test clock, started by the first key. Deleting everything typed restarts it with the
+// next key, so time spent before that doesn't drag the WPM down; the text has been typed on
+// by then, so it can't be regenerated any more.
+#[derive(Default)]
+struct TestClock {
+    start: Option<Instant>,
+    typed_on: bool,
+}
+
+impl TestClock {
+    fn running(&self) -> bool {
+        self.start.is_some()
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.start.map_or(Duration::ZERO, |t| t.elapsed())
+    }
+
+    // A key was typed at `now`. True when it started the clock.
+    fn key(&mut self, now: Instant) -> bool {
+        self.typed_on = true;
+        let first = self.start.is_none();
+        self.start.get_or_insert(now);
+        first
+    }
+
+    // Removes the last typed character, stopping the clock when nothing is left
+    fn delete(&mut self, input: &mut String) {
+        input.pop();
+        if input.is_empty() {
+            self.start = None;
+        }
+    }
+
+    fn can_regenerate(&self) -> bool {
+        !self.typed_on
+    }
+}
+
+// Index of the word containing `pos`; a separator belongs to the word before it.
+fn word_index_at(text: &str, pos: usize, separator: char) -> usize {
+    text.chars().take(pos).filter(|&c| c == separator).count()
+}
+
+// Words whose separator has been reached without a wrong keystroke in them. The empty
+// "words" between doubled separators don't count.
+fn clean_words_done(target: &str, typed: usize, separator: char, failed: &HashSet<usize>) -> usize {
+    target
+        .split(separator)
+        .take(word_index_at(target, typed, separator))
+        .enumerate()
+        .filter(|(i, word)| !word.is_empty() && !failed.contains(i))
+        .count()
+}
+
+#[derive(Debug, PartialEq)]
+enum Completion {
+    Running,
+    Done,
+    LastWordTyped, // Words mode: every letter of the last word, its separator still to come
+}
+
+// Where the input typed so far leaves the test. A Words test ends with its last word's
+// separator (every one of a run of them), even though the generated text has none after
+// the last word; until then it's LastWordTyped, which the final-space grace may end early.
+// Copies of missed words queued by repeat-until-clean (`repeated`) don't use up the limit.
+// Counting clean words, it ends once enough are done. Reaching the end of the target ends
+// every other mode.
+fn test_progress(mode: TestMode, target: &str, input: &str, sep: char, failed: &HashSet<usize>, count_clean_words: bool, repeated: usize) -> Completion {
+    let typed = input.chars().count();
+    let at_end = typed >= target.chars().count();
+    let done = match mode {
+        TestMode::Words(limit) if !count_clean_words && count_words(input, sep).saturating_sub(repeated) >= limit => {
+            let next = target.chars().nth(typed);
+            if !input.ends_with(sep) && (at_end || next == Some(sep)) {
+                return Completion::LastWordTyped;
+            }
+            input.ends_with(sep) && next != Some(sep)
+        }
+        TestMode::Words(limit) if count_clean_words => clean_words_done(target, typed, sep, failed) >= limit,
+        TestMode::Chars(limit) => typed >= limit,
+        TestMode::Words(_) | TestMode::Time(_) | TestMode::Forever | TestMode::Pressure => false,
+    };
+    if done || at_end { Completion::Done } else { Completion::Running }
+}
+
+// When a Words test whose last word is typed ends without its separator. Typing it within
+// the grace still lands in the test rather than the results; a stray key meanwhile doesn't
+// restart the clock. No grace means the separator is required.
+fn grace_deadline(progress: &Completion, grace_ms: u64, current: Option<Instant>, now: Instant) -> Option<Instant> {
+    match progress {
+        Completion::LastWordTyped if grace_ms > 0 => Some(current.unwrap_or(now + Duration::from_millis(grace_ms))),
+        _ => None,
+    }
+}
+
+fn count_words(text: &str, separator: char) -> usize {
+    text.split(separator).filter(|w| !w.is_empty()).count()
+}
+
+// Byte offset of the `pos`-th character, for slicing by typed length
+fn byte_offset(text: &str, pos: usize) -> usize {
+    text.char_indices().nth(pos).map_or(text.len(), |(i, _)| i)
+}
+
+// Char index where each wrapped row starts. Wide (CJK/emoji) characters take two
+// cells and move to the next row whole rather than being split.
+fn wrap_rows(text: &str, width: usize) -> Vec<usize> {
+    let mut rows = vec![0];
+    let mut current_width = 0;
+    for (i, c) in text.chars().enumerate() {
+        // Newlines and other controls are drawn as a single-cell glyph
+        let w = c.width().unwrap_or(1);
+        if current_width + w > width && current_width > 0 {
+            rows.push(i);
+            current_width = 0;
+        }
+        current_width += w;
+    }
+    rows
+}
+
+// What to draw for a target character; never used for matching
+fn display_glyph(c: char, show_whitespace: bool) -> char {
+    match c {
+        '\n' => '↵',
+        ' ' if show_whitespace => '·',
+        _ => c,
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+enum TestMode {
+    Time(u64),
+    Words(usize),
+    Chars(usize),
+    Forever,
+    Pressure, // Every word must beat a shrinking time budget, the first miss ends the run
+}
+
+const MODE_KINDS: [&str; 5] = ["Words", "Time", "Chars", "Forever", "Pressure"];
+
+impl TestMode {
+    // Mode without its limit, the key for per-mode overrides
+    fn kind(&self) -> &'static str {
+        match self {
+            TestMode::Words(_) => "Words",
+            TestMode::Time(_) => "Time",
+            TestMode::Chars(_) => "Chars",
+            TestMode::Forever => "Forever",
+            TestMode::Pressure => "Pressure",
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            TestMode::Time(t) => format!("Time Mode: {}s", t),
+            TestMode::Words(w) => format!("Words Mode: {}", w),
+            TestMode::Chars(c) => format!("Chars Mode: {}", c),
+            TestMode::Forever => "Forever Mode".to_string(),
+            TestMode::Pressure => "Pressure Mode".to_string(),
+        }
+    }
+}
+
+// Raw mode and screen setup for a TUI screen. Everything is undone on drop, so an error
+// returned with `?` halfway through a screen can't leave the shell in raw mode.
+struct TerminalGuard {
+    terminal: Terminal<CrosstermBackend<io::Stdout>>,
+    alt_screen: bool,
+    bracketed_paste: bool,
+}
+
+impl TerminalGuard {
+    fn fullscreen() -> Result<Self> {
+        Self::enter(true, false, 0)
+    }
+
+    // `inline_height` is only used without the alternate screen
+    fn enter(alt_screen: bool, bracketed_paste: bool, inline_height: u16) -> Result<Self> {
+        let setup = || -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+            let mut stdout = io::stdout();
+            if alt_screen {
+                execute!(stdout, EnterAlternateScreen)?;
+            }
+            if bracketed_paste {
+                execute!(stdout, EnableBracketedPaste)?;
+            }
+            let backend = CrosstermBackend::new(stdout);
+            Ok(if alt_screen {
+                Terminal::new(backend)?
+            } else {
+                Terminal::with_options(backend, TerminalOptions { viewport: Viewport::Inline(inline_height) })?
+            })
+        };
+        let terminal = guarded_setup(enable_raw_mode, setup, || restore_terminal(alt_screen, bracketed_paste))?;
+        Ok(Self { terminal, alt_screen, bracketed_paste })
+    }
+}
+
+// `enter` then `setup`; a failing setup runs `restore` before returning its error, so a
+// half-done setup never leaves raw mode behind. A failing `enter` has nothing to undo.
+fn guarded_setup<T>(enter: impl FnOnce() -> io::Result<()>, setup: impl FnOnce() -> Result<T>, restore: impl FnOnce()) -> Result<T> {
+    enter()?;
+    setup().inspect_err(|_| restore())
+}
+
+// Best effort: this runs on error paths, where there is nobody left to report to
+fn restore_terminal(alt_screen: bool, bracketed_paste: bool) {
+    let _ = disable_raw_mode();
+    let mut stdout = io::stdout();
+    if bracketed_paste {
+        let _ = execute!(stdout, DisableBracketedPaste);
+    }
+    if alt_screen {
+        let _ = execute!(stdout, LeaveAlternateScreen);
+    }
+    let _ = execute!(stdout, crossterm::cursor::Show);
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if !self.alt_screen {
+            // Inline: wipe what we drew so the shell (or gum) starts on a clean area
+            let _ = self.terminal.clear();
+        }
+        restore_terminal(self.alt_screen, self.bracketed_paste);
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Terminal<CrosstermBackend<io::Stdout>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+// Time allowed for the word after `survived` words: start * decay^survived
+fn pressure_budget(settings: &Settings, survived: usize) -> Duration {
+    let secs = settings.pressure_start_budget * settings.pressure_decay.powi(survived as i32);
+    Duration::from_secs_f64(secs.max(PRESSURE_MIN_BUDGET))
+}
+
+// Per-mode overrides only last for the run; the user's own settings come back afterwards
+fn run_test(app: &mut AppState, mode: TestMode) -> Result<Option<TestResult>> {
+    let saved = app.settings.clone();
+    app.settings.apply_mode_overrides(mode);
+    log_event!("test start: {}", mode.label());
+    let res = test_screen(app, mode);
+    match &res {
+        Ok(Some(r)) => log_event!("test end: {:.1} WPM, {:.1}% accuracy, {:.1}s, {} chars{}", r.wpm, r.accuracy, r.time_taken, r.text_length,
+            r.invalid_reason.as_ref().map(|why| format!(", not saved: {}", why)).unwrap_or_default()),
+        Ok(None) => log_event!("test end: aborted"),
+        Err(e) => log_event!("test end: error {:#}", e),
+    }
+    app.settings = saved;
+    res
+}
+
+fn test_screen(app: &mut AppState, mode: TestMode) -> Result<Option<TestResult>> {
+    let keys = app.settings.keybindings.resolve().map_err(anyhow::Error::msg)?;
+    let quit_label = app.settings.keybindings.quit.to_uppercase();
+    let regenerate_label = app.settings.keybindings.regenerate.to_uppercase();
+    let forgive_label = app.settings.keybindings.toggle_forgive.to_uppercase();
+    // Inline height: text area plus header, gauge and footer rows
+    let inline_height = if app.settings.large_text { 16 } else { 12 } + 6;
+    let mut terminal = TerminalGuard::enter(app.settings.use_alt_screen, true, inline_height)?;
+
+    let target_count = match mode {
+        TestMode::Words(n) => n,
+        TestMode::Time(_) | TestMode::Chars(_) | TestMode::Forever | TestMode::Pressure => 50,
+    };
+    app.session_letters.clear();
+    let sep = app.settings.word_separator;
+    // Prepared text (preview, daily challenge) was chosen on purpose, so it can't be regenerated
+    let fixed_text = app.prepared_text.is_some();
+    let mut target_text = app.prepared_text.take().unwrap_or_else(|| app.generate_text(target_count));
+    let mut difficulty = app.difficulty_ratio(&target_text);
+    let mut input_text = String::new();
+    
+    let mut last_keystroke = Instant::now();
+    let mut clock = TestClock::default();
+    
+    let mut should_exit = false;
+    let mut completed = false;
+    let mut scroll_offset = 0;
+    let mut failed_words: HashSet<usize> = HashSet::new();
+    let mut paste_detected = false;
+    let mut warmup_deltas: Vec<f64> = Vec::new();
+    let mut repeat_attempts = 0;
+    let mut words_repeated = 0; // Copies queued by repeat-until-clean, on top of the word limit
+    let mut backspaces = 0;
+    let mut enter_hint_until: Option<Instant> = None;
+    let mut error_flash_until: Option<Instant> = None;
+    let mut auto_spaced = false; // Last input was an auto-inserted space
+    let mut below_floor = false; // Run ended early by the accuracy floor
+    let mut dirty = true; // Something changed since the last draw
+    let mut drawn_hints = (false, false); // (error flash, enter hint) as last drawn
+    let mut last_draw = Instant::now();
+    let mut words_survived = 0; // Pressure mode
+    let mut case_slips = 0; // Right letter, wrong case: a shift-timing slip rather than a wrong key
+    let mut streak = 0; // Correct keystrokes since the last error
+    let mut best_streak = 0;
+    let mut word_deadline: Option<Instant> = None;
+    let mut last_autosave = Instant::now();
+    let mut word_start = Instant::now(); // When the current word's first key could be pressed
+    let mut pending_base: Option<char> = None; // Decomposed input waiting for its combining mark
+    let mut blocked_at: Option<usize> = None; // Position where a wrong key was blocked, already counted in the stats
+    let mut final_grace: Option<Instant> = None; // Last word typed, no separator yet: the test ends at this moment unless it comes
+    let mut recent_errors: VecDeque<(char, char)> = VecDeque::new(); // (expected, typed), newest last
+    let mut replay: Option<Event> = None; // Key to handle on the next pass without reading a new one
+    let mut autosave: Option<std::thread::JoinHandle<io::Result<()>>> = None;
+
+    while !should_exit && !completed {
+        if app.interrupted.load(Ordering::Relaxed) {
+            break;
+        }
+        let elapsed = clock.elapsed();
+        let typed = input_text.chars().count();
+        let wpm = if elapsed.as_secs_f64() > 0.0 {
+             (typed as f64 / 5.0) / (elapsed.as_secs_f64() / 60.0)
+        } else {
+            0.0
+        };
+
+        // Running accuracy over this test's keystrokes
+        let (shown, correct) = app.session_letters.values().fold((0, 0), |(s, c), &(shown, correct)| (s + shown, c + correct));
+        let accuracy = if shown > 0 { correct as f64 / shown as f64 * 100.0 } else { 100.0 };
+        let floor_hit = app.settings.accuracy_floor > 0.0 && shown >= FLOOR_MIN_KEYSTROKES && accuracy < app.settings.accuracy_floor;
+        if floor_hit && app.settings.accuracy_floor_ends_test {
+            below_floor = true;
+            completed = true;
+            break;
+        }
+
+        // Check if Time Mode is finished
+        if let TestMode::Time(limit) = mode {
+            if clock.running() && elapsed.as_secs() >= limit {
+                completed = true;
+                break;
+            }
+        }
+
+        if final_grace.is_some_and(|t| Instant::now() >= t) {
+            completed = true;
+            break;
+        }
+
+        // Pressure mode: the clock for the current word starts with the first key
+        let word_time_left = clock.start.filter(|_| mode == TestMode::Pressure).map(|start| {
+            let deadline = *word_deadline.get_or_insert_with(|| start + pressure_budget(&app.settings, 0));
+            deadline.saturating_duration_since(Instant::now())
+        });
+        if word_time_left == Some(Duration::ZERO) {
+            completed = true;
+            break;
+        }
+
+        // Buffer management for continuous modes; counting clean words, errors make a Words test longer
+        let continuous = match mode {
+            TestMode::Words(_) => app.settings.count_clean_words,
+            TestMode::Time(_) | TestMode::Chars(_) | TestMode::Forever | TestMode::Pressure => true,
+        };
+        if continuous && typed + 50 > target_text.chars().count() {
+            let more = app.generate_text(20);
+            target_text.push(sep);
+            target_text.push_str(&more);
+        }
+
+        // Draw UI
+        let large_text = app.settings.large_text;
+        let theme = app.settings.theme;
+        let error_flash = error_flash_until.is_some_and(|t| Instant::now() < t);
+        let enter_hint = enter_hint_until.is_some_and(|t| Instant::now() < t);
+        // Only redraw on input, when a timed hint appears/expires, or for the clock tick
+        let distracting = app.settings.distractions && clock.running();
+        let tick = if word_time_left.is_some() || distracting { PRESSURE_TICK } else { CLOCK_TICK };
+        let redraw = dirty || (error_flash, enter_hint) != drawn_hints || last_draw.elapsed() >= tick;
+        if redraw {
+            dirty = false;
+            drawn_hints = (error_flash, enter_hint);
+            last_draw = Instant::now();
+            terminal.draw(|f| {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Fill(1),
+                        Constraint::Length(if large_text { 16 } else { 12 }),
+                        Constraint::Min(1),
+                        Constraint::Length(1),
+                    ])
+                    .split(f.size());
+
+                // Header Area
+                let mode_str = mode.label();
+                let mode_str = if app.is_ephemeral() { format!("[EPHEMERAL] {}", mode_str) } else { mode_str };
+            
+                let wpm_str = if app.settings.show_wpm_live { format!(" | WPM: {:.0}", wpm) } else { String::new() };
+                let status = if clock.running() {
+                    match mode {
+                        TestMode::Time(limit) => format!("{} | Time Left: {:.0}s{}", mode_str, (limit as f64 - elapsed.as_secs_f64()).max(0.0), wpm_str),
+                        TestMode::Words(limit) => {
+                            let done = if app.settings.count_clean_words {
+                                clean_words_done(&target_text, typed, sep, &failed_words)
+                            } else {
+                                count_words(&input_text, sep).saturating_sub(words_repeated)
+                            };
+                            let remaining = limit.saturating_sub(done);
+                            let eta = if wpm > 0.0 && app.settings.show_wpm_live {
+                                format!(" | ETA: {:.0}s", remaining as f64 / wpm * 60.0)
+                            } else {
+                                String::new()
+                            };
+                            let progress = app.settings.progress_format.render(done, limit);
+                            format!("{} | Time: {:.0}s{} | {}{}", mode_str, elapsed.as_secs_f64(), wpm_str, progress, eta)
+                        }
+                        TestMode::Chars(limit) => format!("{} | Time: {:.0}s{} | {}/{}", mode_str, elapsed.as_secs_f64(), wpm_str, input_text.chars().count(), limit),
+                        TestMode::Pressure => format!(
+                            "{} | Word: {:.1}s{} | Survived: {}",
+                            mode_str,
+                            word_time_left.unwrap_or_default().as_secs_f64(),
+                            wpm_str,
+                            words_survived
+                        ),
+                        _ => format!("{} | Time: {:.0}s{}", mode_str, elapsed.as_secs_f64(), wpm_str),
+                    }
+                } else {
+                    format!("{} | Press any key to start typing...", mode_str)
+                };
+                let status = if paste_detected {
+                    format!("{} | PASTE DETECTED - result won't be saved", status)
+                } else {
+                    status
+                };
+                let mut status_line = vec![Span::raw(status)];
+                status_line.push(Span::raw(format!(" | Forgive ({}): {}", forgive_label, if app.forgive_errors() { "On" } else { "Off" })));
+                if clock.running() && (app.settings.show_wpm_live || app.settings.accuracy_floor > 0.0) {
+                    let style = if floor_hit { Style::default().fg(Color::Red) } else { Style::default() };
+                    status_line.push(Span::styled(format!(" | Acc: {:.0}%", accuracy), style));
+                }
+                if clock.running() && app.settings.show_wpm_live {
+                    // Past the all-time record: make it obvious
+                    let record = app.user_data.best_streak > 0 && streak > app.user_data.best_streak;
+                    let (text, style) = if record {
+                        (format!(" | Streak: {} RECORD!", streak), Style::default().fg(Color::Rgb(7, 206, 65)))
+                    } else {
+                        (format!(" | Streak: {}", streak), Style::default())
+                    };
+                    status_line.push(Span::styled(text, style));
+                }
+
+                f.render_widget(
+                    Paragraph::new(Line::from(status_line)).style(theme.header()).alignment(Alignment::Center).block(Block::default().borders(Borders::BOTTOM)),
+                    layout[0]
+                );
+
+                // Typing Text Area
+                // Distractions: the background drifts through dim tints (period ~8s)
+                let phase = elapsed.as_secs_f64();
+                let text_bg = if distracting {
+                    let wave = |offset: f64| (20.0 + 14.0 * (1.0 + (phase * 0.8 + offset).sin())) as u8;
+                    Color::Rgb(wave(0.0), wave(2.1), wave(4.2))
+                } else {
+                    Color::Rgb(20, 20, 20)
+                };
+                let text_block = Block::default()
+                    .padding(if large_text { ratatui::widgets::Padding::new(6,6,2,2) } else { ratatui::widgets::Padding::new(2,2,1,1) })
+                    .style(Style::default().bg(text_bg));
+                let mut text_area = text_block.inner(layout[1]);
+                f.render_widget(text_block, layout[1]);
+                // The minimap takes the rightmost cell, plus one cell of gap
+                let minimap_area = (app.settings.show_minimap && text_area.width > 10).then(|| {
+                    text_area.width -= 2;
+                    Rect { x: text_area.right() + 1, width: 1, ..text_area }
+                });
+                // Blind mode hides what was wrong, so it gets no ticker
+                let ticker_area = (app.settings.show_error_ticker && !app.settings.blind_mode && text_area.width > 30).then(|| {
+                    text_area.width -= ERROR_TICKER_WIDTH + 1;
+                    Rect { x: text_area.right() + 1, width: ERROR_TICKER_WIDTH, ..text_area }
+                });
+                if let Some(area) = ticker_area {
+                    let mut lines = vec![Line::from(Span::styled("Errors", Style::default().fg(Color::DarkGray)))];
+                    lines.extend(recent_errors.iter().rev().map(|&(expected, got)| {
+                        Line::from(vec![
+                            Span::styled(ticker_char(expected), Style::default().fg(themed("CORRECT", Color::Green))),
+                            Span::styled("→", Style::default().fg(Color::DarkGray)),
+                            Span::styled(ticker_char(got), Style::default().fg(themed("INCORRECT", Color::Red))),
+                        ])
+                    }));
+                    f.render_widget(Paragraph::new(lines), area);
+                }
+
+                // Newspaper-style columns: rows fill the left column first, then continue on the right
+                let columns = app.settings.columns.clamp(1, 2) as usize;
+                let column_gap = 4;
+                let width = ((text_area.width as usize).saturating_sub(column_gap * (columns - 1)) / columns).max(1);
+                // Large text: every glyph is followed by a blank cell and every row by a blank line
+                let spacing = if large_text { 2 } else { 1 };
+                let wrap_width = (width / spacing).max(1);
+                let visible_lines = (text_area.height as usize / spacing).max(1);
+                let rows_on_screen = visible_lines * columns;
+                let rows = wrap_rows(&target_text, wrap_width);
+                let cursor_row = rows.partition_point(|&start| start <= typed).saturating_sub(1);
+            
+                // Auto scroll
+                match app.settings.scroll_policy {
+                    ScrollPolicy::Center => {
+                        if cursor_row > scroll_offset + rows_on_screen / 2 {
+                            scroll_offset = cursor_row - rows_on_screen / 2;
+                        }
+                    }
+                    ScrollPolicy::Margin => {
+                        let lowest_row = rows_on_screen.saturating_sub(1 + app.settings.scroll_margin);
+                        if cursor_row > scroll_offset + lowest_row {
+                            scroll_offset = cursor_row - lowest_row;
+                        }
+                    }
+                }
+                // Backspacing past the top brings the cursor row back into view
+                scroll_offset = scroll_offset.min(cursor_row);
+            
+                let typed_chars: Vec<char> = input_text.chars().collect();
+                let target_chars: Vec<char> = target_text.chars().collect();
+
+                if let Some(area) = minimap_area {
+                    let total = target_chars.len().max(1);
+                    let height = area.height as usize;
+                    let cells: Vec<Line> = (0..height)
+                        .map(|r| {
+                            // Each cell covers an equal share of the target, at least one character
+                            let start = r * total / height;
+                            let end = ((r + 1) * total / height).max(start + 1);
+                            let mistake = (start..end.min(typed)).any(|i| typed_chars.get(i) != target_chars.get(i));
+                            let style = if (start..end).contains(&typed) {
+                                Style::default().fg(themed("CURSOR", Color::Blue))
+                            } else if mistake && !app.settings.blind_mode {
+                                Style::default().fg(themed("INCORRECT", Color::Red))
+                            } else if end <= typed {
+                                Style::default().fg(if app.settings.blind_mode { Color::White } else { themed("CORRECT", Color::Green) })
+                            } else {
+                                Style::default().fg(Color::DarkGray)
+                            };
+                            Line::from(Span::styled("█", style))
+                        })
+                        .collect();
+                    f.render_widget(Paragraph::new(cells), area);
+                }
+
+                let mut spans = Vec::new();
+                for row in scroll_offset..(scroll_offset + rows_on_screen).min(rows.len()) {
+                    let end = rows.get(row + 1).copied().unwrap_or(target_chars.len());
+                    let line: Vec<Span> = (rows[row]..end)
+                        .map(|absolute_idx| {
+                            let c = target_chars[absolute_idx];
+                            let style = if app.settings.blind_mode && absolute_idx < typed {
+                                // Typed text gives no hint of correctness
+                                Style::default().fg(Color::White)
+                            } else if error_flash && absolute_idx == typed {
+                                Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)
+                            } else {
+                                char_style(c, typed_chars.get(absolute_idx).copied(), absolute_idx == typed)
+                            };
+
+                            // Display only: matching above still compares against the real character
+                            // A run of spaces is always drawn visibly, so every one of them can be seen
+                            let in_run = c == ' '
+                                && (target_chars.get(absolute_idx + 1) == Some(&' ')
+                                    || (absolute_idx > 0 && target_chars[absolute_idx - 1] == ' '));
+                            let glyph = display_glyph(c, app.settings.show_whitespace || in_run);
+                            if large_text {
+                                Span::styled(format!("{} ", glyph), style.add_modifier(Modifier::BOLD))
+                            } else {
+                                Span::styled(glyph.to_string(), style)
+                            }
+                        })
+                        .collect();
+                    spans.push(Line::from(line));
+                }
+
+                for col in 0..columns {
+                    let column_lines: Vec<Line> = spans
+                        .iter()
+                        .skip(col * visible_lines)
+                        .take(visible_lines)
+                        .flat_map(|line| std::iter::once(line.clone()).chain((spacing > 1).then(Line::default)))
+                        .collect();
+                    let area = Rect {
+                        x: text_area.x + (col * (width + column_gap)) as u16,
+                        width: width as u16,
+                        ..text_area
+                    };
+                    f.render_widget(Paragraph::new(column_lines), area);
+                }
+
+                // Distractions: a dot bouncing along the text block's top padding row
+                if distracting && layout[1].width > 0 {
+                    let span = layout[1].width as f64 - 1.0;
+                    let pos = (phase * 12.0) % (2.0 * span.max(1.0));
+                    let x = if pos > span { 2.0 * span - pos } else { pos };
+                    f.render_widget(
+                        Paragraph::new("●").style(Style::default().fg(Color::Rgb(200, 120, 40)).bg(text_bg)),
+                        Rect { x: layout[1].x + x as u16, y: layout[1].y, width: 1, height: 1 }
+                    );
+                }
+
+                // Pre-test difficulty overlay, sits just above the text
+                if clock.can_regenerate() {
+                    let label = difficulty_label(difficulty);
+                    let color = match label {
+                        "Easy" => Color::Green,
+                        "Medium" => Color::Yellow,
+                        _ => Color::Red,
+                    };
+                    let text = Line::from(vec![
+                        Span::raw("Difficulty: "),
+                        Span::styled(format!("{} ({:.2}x)", label, difficulty), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                        Span::styled(if fixed_text { String::new() } else { format!("  {}: regenerate", regenerate_label) }, Style::default().fg(Color::Gray)),
+                    ]);
+                    let w = (text.width() as u16 + 4).min(f.size().width);
+                    let area = Rect {
+                        x: f.size().width.saturating_sub(w) / 2,
+                        y: layout[1].y.saturating_sub(3),
+                        width: w,
+                        height: 3,
+                    };
+                    f.render_widget(ratatui::widgets::Clear, area);
+                    f.render_widget(Paragraph::new(text).alignment(Alignment::Center).block(Block::default().borders(Borders::ALL)), area);
+                }
+
+                // Footer Area
+                let footer = if enter_hint {
+                    format!("{}: Quit | Enter is ignored (Settings > Enter as Space)", quit_label)
+                } else {
+                    format!("{}: Quit", quit_label)
+                };
+                f.render_widget(
+                    Paragraph::new(footer).alignment(Alignment::Center).style(theme.footer()),
+                    layout[2]
+                );
+
+                if app.settings.show_warmup {
+                    let level = warmup_level(&warmup_deltas);
+                    let label = if warmup_deltas.len() >= WARMUP_SAMPLES || level >= 0.8 {
+                        format!("Warmed up: {:.0}%", level * 100.0)
+                    } else {
+                        format!("Warming up: {:.0}%", level * 100.0)
+                    };
+                    f.render_widget(
+                        Gauge::default().ratio(level).label(label).gauge_style(Style::default().fg(Color::Rgb(7, 206, 65)).bg(Color::Black)),
+                        layout[3]
+                    );
+                }
+
+            })?; // End of draw closure
+        }
+
+        // Periodic autosave: serialising is quick, the file write happens on a thread.
+        // A failed write is caught by the save at the end of the test.
+        let autosave_every = app.settings.autosave_secs;
+        if autosave_every > 0 && app.unsaved_stats && !app.is_ephemeral() && app.can_write("userdata.json") && last_autosave.elapsed().as_secs() >= autosave_every {
+            last_autosave = Instant::now();
+            if let Ok(json) = serde_json::to_string_pretty(&app.user_data) {
+                if let Some(previous) = autosave.take() {
+                    let _ = previous.join();
+                }
+                autosave = Some(std::thread::spawn(move || fs::write("userdata.json", json)));
+            }
+        }
+
+        // Input Handling
+        let ev = match replay.take() {
+            Some(ev) => Some(ev),
+            None if event::poll(Duration::from_millis(16))? => Some(event::read()?),
+            None => None,
+        };
+        if let Some(ev) = ev {
+            dirty = true;
+            // Pasted text never reaches the buffer, only the policy decides what else happens
+            if let Event::Paste(_) = ev {
+                if clock.running() && app.settings.paste_policy == PastePolicy::Invalidate {
+                    paste_detected = true;
+                }
+            }
+            if let Event::Key(key) = ev {
+                if key.kind == KeyEventKind::Press {
+                    let code = enter_key(key.code, app.settings.enter_as_space, sep);
+                    match code {
+                        // Raw mode swallows SIGINT, so Ctrl+C arrives here as a key
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.interrupted.store(true, Ordering::Relaxed);
+                        }
+                        _ if keys.quit.matches(&key) => should_exit = true,
+                        _ if keys.regenerate.matches(&key) && clock.can_regenerate() && !fixed_text => {
+                            target_text = app.generate_text(target_count);
+                            difficulty = app.difficulty_ratio(&target_text);
+                            blocked_at = None;
+                        }
+                        // An exam never blocks wrong keys, so there's nothing to toggle
+                        _ if keys.toggle_forgive.matches(&key) && app.exam => {}
+                        // Only decides what later wrong keys do; what's already typed stays as it is
+                        _ if keys.toggle_forgive.matches(&key) => app.session_forgive = Some(!app.forgive_errors()),
+                        _ if keys.delete.matches(&key) && pending_base.is_some() => pending_base = None,
+                        _ if keys.delete.matches(&key) && !input_text.is_empty() => {
+                            auto_spaced = false;
+                            final_grace = None;
+                            backspaces += 1;
+                            clock.delete(&mut input_text);
+                            if !clock.running() {
+                                word_deadline = None;
+                            }
+                        }
+                        KeyCode::Enter => enter_hint_until = Some(Instant::now() + Duration::from_millis(1500)),
+                        // Habitual separator right after an auto-inserted one
+                        KeyCode::Char(c) if auto_spaced && c == sep => auto_spaced = false,
+                        KeyCode::Char(c) => {
+                            // Decomposed input ("e", then U+0301) for a precomposed target ("é"): the
+                            // base waits for its mark so the pair is judged once, as the composed
+                            // character. Any other key judges the base alone and is replayed next pass.
+                            let target = target_text.chars().nth(typed);
+                            let c = match pending_base.take() {
+                                Some(base) => match unicode_normalization::char::compose(base, c) {
+                                    Some(composed) => composed,
+                                    None => {
+                                        replay = Some(Event::Key(key));
+                                        base
+                                    }
+                                },
+                                None => c,
+                            };
+                            if replay.is_none() && awaits_mark(c, target) {
+                                pending_base = Some(c);
+                                continue;
+                            }
+                            auto_spaced = false;
+                            // The clock starts on this key, so its delta is meaningless
+                            let now = Instant::now();
+                            let first_keystroke = clock.key(now);
+                            if first_keystroke {
+                                last_keystroke = now;
+                                word_start = now;
+                            }
+
+                            // The last word's separator: generated text doesn't end with one, so it's added
+                            // here and typed like any other. Reaching the end otherwise completes the test
+                            // (below), so a key always has a target.
+                            if c == sep && typed == target_text.chars().count() {
+                                target_text.push(sep);
+                            }
+                            if let Some(target_char) = target_text.chars().nth(typed) {
+                                let now = Instant::now();
+                                let delta = now.duration_since(last_keystroke).as_secs_f64();
+                                last_keystroke = now;
+                                if !first_keystroke && warmup_deltas.len() < WARMUP_SAMPLES {
+                                    warmup_deltas.push(delta);
+                                }
+
+                                let is_correct = c == target_char;
+                                let retry = blocked_at == Some(typed);
+
+                                app.update_stats(target_char, c, (!first_keystroke).then_some(delta), retry);
+                                if !retry {
+                                    let session = app.session_letters.entry(target_char).or_insert((0, 0));
+                                    session.0 += 1;
+                                    if is_correct {
+                                        session.1 += 1;
+                                    }
+                                }
+                                if is_correct {
+                                    streak += 1;
+                                    best_streak = best_streak.max(streak);
+                                } else {
+                                    streak = 0;
+                                }
+                                if !is_correct {
+                                    if recent_errors.len() == ERROR_TICKER_LEN {
+                                        recent_errors.pop_front();
+                                    }
+                                    recent_errors.push_back((target_char, c));
+                                    failed_words.insert(word_index_at(&target_text, typed, sep));
+                                    if c.to_lowercase().eq(target_char.to_lowercase()) {
+                                        case_slips += 1;
+                                    }
+                                }
+
+                                if is_correct || !(app.forgive_errors() || app.settings.enforce_correction) {
+                                    blocked_at = None;
+                                    input_text.push(c);
+                                    let typed = typed + 1;
+                                    let mut finished_word = target_char == sep;
+
+                                    // Auto-space: a cleanly typed word gets its separator for free. It isn't
+                                    // a keystroke, so it never touches the letter stats.
+                                    if app.settings.auto_space && !finished_word && target_text.chars().nth(typed) == Some(sep) {
+                                        let head = &target_text[..byte_offset(&target_text, typed)];
+                                        let word = head.rsplit(sep).next().unwrap_or_default();
+                                        if input_text.chars().skip(typed - word.chars().count()).eq(word.chars()) {
+                                            input_text.push(sep);
+                                            auto_spaced = true;
+                                            finished_word = true;
+                                        }
+                                    }
+
+                                    if finished_word {
+                                        let word_idx = word_index_at(&target_text, typed - 1, sep);
+                                        let len = target_text.split(sep).nth(word_idx).map_or(0, |w| w.chars().count());
+                                        app.record_word(len, !failed_words.contains(&word_idx), now.duration_since(word_start).as_secs_f64());
+                                        word_start = now;
+                                    }
+
+                                    if finished_word && mode == TestMode::Pressure {
+                                        words_survived += 1;
+                                        word_deadline = Some(Instant::now() + pressure_budget(&app.settings, words_survived));
+                                    }
+
+                                    // Word just finished: queue it again if it had mistakes
+                                    if finished_word && app.settings.repeat_until_clean {
+                                        let typed = input_text.chars().count();
+                                        let word_idx = word_index_at(&target_text, typed - 1, sep);
+                                        if failed_words.contains(&word_idx) && repeat_attempts < app.settings.repeat_max_attempts {
+                                            let word = target_text.split(sep).nth(word_idx).unwrap_or_default().to_string();
+                                            target_text.insert_str(byte_offset(&target_text, typed), &format!("{}{}", word, sep));
+                                            repeat_attempts += 1;
+                                            words_repeated += 1;
+                                        } else {
+                                            repeat_attempts = 0;
+                                        }
+                                    }
+                                } else if app.settings.enforce_correction {
+                                    // Stays put like forgive-errors, but makes the miss visible
+                                    error_flash_until = Some(now + Duration::from_millis(200));
+                                    blocked_at = Some(typed);
+                                } else if app.forgive_errors() && !is_correct {
+                                    // Block input
+                                    blocked_at = Some(typed);
+                                }
+                            }
+
+                            let progress = test_progress(mode, &target_text, &input_text, sep, &failed_words, app.settings.count_clean_words, words_repeated);
+                            completed |= progress == Completion::Done;
+                            final_grace = grace_deadline(&progress, app.settings.final_space_grace_ms, final_grace, Instant::now());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    } // End of While Loop
+    app.session_letters.clear();
+    // Don't let an autosave race the save that follows the test
+    if let Some(previous) = autosave {
+        let _ = previous.join();
+    }
+
+    if completed {
+        if app.settings.completion_sound {
+            execute!(terminal.backend_mut(), crossterm::style::Print("\x07"))?;
+        }
+        if app.settings.completion_animation {
+            play_completion_animation(&mut terminal)?;
+        }
+    }
+
+    drop(terminal);
+
+    if completed {
+        let elapsed = clock.elapsed().as_secs_f64();
+        let chars = input_text.chars().count();
+        let words = count_words(&input_text, sep);
+        let penalised_chars = (chars as f64 - backspaces as f64 * app.settings.backspace_penalty).max(0.0);
+        let raw_wpm = (penalised_chars / 5.0) / (elapsed / 60.0);
+        
+        let correct_chars = input_text.chars().zip(target_text.chars()).filter(|(typed, target)| typed == target).count();
+        let accuracy = if chars > 0 { correct_chars as f64 / chars as f64 } else { 0.0 };
+
+        let words_reached = if chars > 0 { word_index_at(&target_text, chars - 1, sep) + 1 } else { 0 };
+        let clean_words = (0..words_reached).filter(|i| !failed_words.contains(i)).count();
+        let word_accuracy = if words_reached > 0 { clean_words as f64 / words_reached as f64 } else { 0.0 };
+
+        let mut missed_words: Vec<String> = target_text
+            .split(sep)
+            .take(words_reached)
+            .enumerate()
+            .filter(|(i, word)| failed_words.contains(i) && !word.is_empty())
+            .map(|(_, word)| word.to_string())
+            .collect();
+        missed_words.sort();
+        missed_words.dedup();
+
+        let net_wpm = if app.settings.fail_words {
+            // Only clean words (and their separator) earn credit
+            let clean_chars: usize = target_text
+                .split(sep)
+                .take(words_reached)
+                .enumerate()
+                .filter(|(i, _)| !failed_words.contains(i))
+                .map(|(_, word)| word.chars().count() + 1)
+                .sum();
+            (clean_chars.min(chars) as f64 / 5.0) / (elapsed / 60.0)
+        } else {
+            raw_wpm * accuracy
+        };
+
+        Ok(Some(TestResult {
+            timestamp: Local::now(),
+            raw_wpm,
+            wpm: net_wpm,
+            accuracy: accuracy * 100.0,
+            time_taken: elapsed,
+            text_length: chars,
+            words_typed: words,
+            word_accuracy: word_accuracy * 100.0,
+            backspaces,
+            mode: Some(mode),
+            words_survived: (mode == TestMode::Pressure).then_some(words_survived),
+            distractions: app.settings.distractions,
+            case_slips: app.settings.case_slips.then_some(case_slips),
+            char_breakdown: Some(CharBreakdown::compare(&input_text, &target_text, sep)),
+            daily: None,
+            best_streak,
+            streak_record: app.user_data.best_streak > 0 && best_streak > app.user_data.best_streak,
+            failed_words: app.settings.fail_words.then_some(words_reached - clean_words),
+            below_floor: below_floor.then_some(app.settings.accuracy_floor),
+            invalid_reason: paste_detected.then(|| "Text was pasted during the test".to_string()),
+            typed_text: input_text,
+            missed_words,
+            target_text,
+        }))
+    } else {
+        Ok(None)
+    }
+}
+
+// Brief flash with a checkmark (~400ms) before the results; any key skips it.
+fn play_completion_animation(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
+    let frames = [Color::Rgb(7, 206, 65), Color::Rgb(5, 140, 45), Color::Rgb(3, 80, 26), Color::Rgb(20, 20, 20)];
+    for bg in frames {
+        terminal.draw(|f| {
+            let area = f.size();
+            f.render_widget(Block::default().bg(bg), area);
+            let banner = Rect {
+                x: area.x,
+                y: area.y + area.height.saturating_sub(2) / 2,
+                width: area.width,
+                height: 2.min(area.height),
+            };
+            f.render_widget(
+                Paragraph::new(vec![Line::from("✔"), Line::from("Complete!")]).alignment(Alignment::Center).bold().fg(Color::White),
+                banner
+            );
+        })?;
+        if event::poll(Duration::from_millis(100))? {
+            let _ = event::read()?;
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Draws a read-only view and blocks until any key is pressed.
+fn show_static_screen<F: Fn(&mut Frame)>(draw: F) -> Result<()> {
+    let mut terminal = TerminalGuard::fullscreen()?;
+
+    loop {
+        terminal.draw(&draw)?;
+        // Any other event (e.g. resize) just triggers a redraw
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Side-by-side view of two runs; deltas are `b - a`, so pass the older run first.
+fn compare_results(a: &TestResult, b: &TestResult, theme: Theme, nf: NumberFormat) -> Result<()> {
+    // (label, a, b, lower_is_better)
+    let metrics = [
+        ("WPM", a.wpm, b.wpm, false),
+        ("Raw WPM", a.raw_wpm, b.raw_wpm, false),
+        ("Accuracy %", a.accuracy, b.accuracy, false),
+        ("Time (s)", a.time_taken, b.time_taken, true),
+    ];
+
+    show_static_screen(|f| {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(6), Constraint::Length(1)])
+            .split(f.size());
+
+        f.render_widget(
+            Paragraph::new("Compare Results").style(theme.header()).alignment(Alignment::Center),
+            layout[0]
+        );
+
+        let header = Row::new(vec![
+            Cell::from(""),
+            Cell::from(a.timestamp.format("%Y-%m-%d %H:%M").to_string()),
+            Cell::from(b.timestamp.format("%Y-%m-%d %H:%M").to_string()),
+            Cell::from("Delta"),
+        ]).bold();
+
+        let rows: Vec<Row> = metrics.iter().map(|&(label, old, new, lower_is_better)| {
+            let delta = new - old;
+            let improved = if lower_is_better { delta < 0.0 } else { delta > 0.0 };
+            let color = if delta.abs() < 0.005 {
+                Color::Gray
+            } else if improved {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            Row::new(vec![
+                Cell::from(label),
+                Cell::from(nf.float(old, 2)),
+                Cell::from(nf.float(new, 2)),
+                Cell::from(format!("{}{}", if delta >= 0.0 { "+" } else { "" }, nf.float(delta, 2))).style(Style::default().fg(color).add_modifier(Modifier::BOLD)),
+            ])
+        }).collect();
+
+        let widths = [Constraint::Length(12), Constraint::Length(18), Constraint::Length(18), Constraint::Length(10)];
+        f.render_widget(
+            Table::new(rows, widths).header(header).block(Block::default().borders(Borders::ALL).padding(ratatui::widgets::Padding::new(2,2,1,1))),
+            layout[1]
+        );
+
+        f.render_widget(
+            Paragraph::new("Press any key to go back").alignment(Alignment::Center).style(theme.footer()),
+            layout[2]
+        );
+    })
+}
+
+// The typed part of the text with the same colouring as the live test
+fn review_mistakes(res: &TestResult, theme: Theme) -> Result<()> {
+    let typed: Vec<char> = res.typed_text.chars().collect();
+    let spans: Vec<Span> = res.target_text
+        .chars()
+        .take(typed.len())
+        .enumerate()
+        .map(|(i, c)| {
+            let style = char_style(c, typed.get(i).copied(), false);
+            // A missed space is invisible otherwise
+            let glyph = display_glyph(c, typed[i] != c);
+            Span::styled(glyph.to_string(), style)
+        })
+        .collect();
+    let errors = typed.iter().zip(res.target_text.chars()).filter(|(t, c)| *t != c).count();
+
+    show_static_screen(|f| {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(3), Constraint::Length(1)])
+            .split(f.size());
+
+        f.render_widget(
+            Paragraph::new(format!("Review Mistakes | {} wrong characters", errors)).style(theme.header()).alignment(Alignment::Center),
+            layout[0]
+        );
+        f.render_widget(
+            Paragraph::new(Line::from(spans.clone()))
+                .wrap(Wrap { trim: false })
+                .block(Block::default().padding(ratatui::widgets::Padding::new(2,2,1,1)))
+                .style(Style::default().bg(Color::Rgb(20, 20, 20))),
+            layout[1]
+        );
+        f.render_widget(
+            Paragraph::new("Press any key to go back").alignment(Alignment::Center).style(theme.footer()),
+            layout[2]
+        );
+    })
+}
+
+// Counts per WPM range as (range start, count), with empty ranges kept so gaps show up
+fn wpm_histogram(history: &[TestResult], bucket_size: f64) -> Vec<(u32, u64)> {
+    let bucket_of = |wpm: f64| (wpm.max(0.0) / bucket_size) as u32;
+    let (Some(lo), Some(hi)) = (
+        history.iter().map(|r| bucket_of(r.wpm)).min(),
+        history.iter().map(|r| bucket_of(r.wpm)).max(),
+    ) else {
+        return Vec::new();
+    };
+
+    (lo..=hi)
+        .map(|b| {
+            let count = history.iter().filter(|r| bucket_of(r.wpm) == b).count() as u64;
+            ((b as f64 * bucket_size) as u32, count)
+        })
+        .collect()
+}
+
+fn show_wpm_histogram(history: &[TestResult], theme: Theme) -> Result<()> {
+    // Wider buckets once the spread gets large, so bars stay readable
+    let spread = history.iter().map(|r| r.wpm).fold(0.0, f64::max);
+    let bucket_size = if spread > 150.0 { 20.0 } else { 10.0 };
+    let buckets = wpm_histogram(history, bucket_size);
+    let bars: Vec<Bar> = buckets
+        .iter()
+        .map(|&(start, count)| Bar::default().value(count).label(Line::from(format!("{}-{}", start, start + bucket_size as u32 - 1))))
+        .collect();
+
+    show_static_screen(|f| {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(5), Constraint::Length(1)])
+            .split(f.size());
+
+        f.render_widget(
+            Paragraph::new(format!("WPM Distribution | {} tests", history.len())).style(theme.header()).alignment(Alignment::Center),
+            layout[0]
+        );
+        f.render_widget(
+            BarChart::default()
+                .data(BarGroup::default().bars(&bars))
+                .bar_width(7)
+                .bar_gap(1)
+                .bar_style(Style::default().fg(Color::Rgb(7, 206, 65)))
+                .value_style(Style::default().fg(Color::Black).bg(Color::Rgb(7, 206, 65)))
+                .block(Block::default().borders(Borders::ALL).padding(ratatui::widgets::Padding::new(1,1,1,0))),
+            layout[1]
+        );
+        f.render_widget(
+            Paragraph::new("Press any key to go back").alignment(Alignment::Center).style(theme.footer()),
+            layout[2]
+        );
+    })
+}
+
+// --- Menus ---
+
+// The index stays unformatted: Compare Two Runs parses it back out of the label
+fn history_label(idx: usize, res: &TestResult, nf: NumberFormat) -> String {
+    format!(
+        "#{} {} | {} WPM | {}%",
+        idx + 1, res.timestamp.format("%Y-%m-%d %H:%M"), nf.float(res.wpm, 1), nf.float(res.accuracy, 1)
+    )
+}
+
+fn format_overview(data: &UserData, settings: &Settings) -> String {
+    let nf = settings.number_format;
+    let mut text = format!(
+        "Tests: {}\nAverage WPM: {}\nAverage Accuracy: {}%\nBest WPM: {}",
+        nf.int(data.total_tests()),
+        nf.float(data.average_wpm().unwrap_or(0.0), 2),
+        nf.float(data.average_accuracy().unwrap_or(0.0), 2),
+        nf.float(data.best_wpm().unwrap_or(0.0), 2),
+    );
+    if data.best_streak > 0 {
+        text.push_str(&format!("\nBest Streak: {} keys", nf.int(data.best_streak)));
+    }
+    if let Some(latest) = data.test_history.last() {
+        text.push_str(&format!("\nLatest: {}", benchmark_band(&settings.benchmark_wpm, latest.wpm)));
+    }
+    let weakest = data.weakest_letters(5);
+    if !weakest.is_empty() {
+        text.push_str("\n\nWeakest letters:");
+        for s in weakest {
+            text.push_str(&format!(
+                "\n  {}  {}% | {} WPM | {} seen",
+                s.letter, nf.float(s.accuracy * 100.0, 1), nf.float(s.wpm, 0), nf.int(s.shown as usize)
+            ));
+        }
+    }
+    text
+}
+
+fn format_word_lengths(data: &UserData, nf: NumberFormat) -> String {
+    if data.word_lengths.is_empty() {
+        return "No words recorded yet.".to_string();
+    }
+    let mut lengths: Vec<_> = data.word_lengths.iter().collect();
+    lengths.sort_by_key(|(len, _)| **len);
+    let mut text = String::from("Length  Words  Accuracy     WPM");
+    for (&len, stat) in lengths {
+        let label = if len >= MAX_WORD_LENGTH { format!("{}+", len) } else { len.to_string() };
+        let accuracy = stat.clean as f64 / stat.words.max(1) as f64 * 100.0;
+        let wpm = if stat.secs > 0.0 { (stat.chars as f64 / 5.0) / (stat.secs / 60.0) } else { 0.0 };
+        text.push_str(&format!("\n{:>6}  {:>5}  {:>7}%  {:>6}", label, nf.int(stat.words as usize), nf.float(accuracy, 1), nf.float(wpm, 1)));
+    }
+    text
+}
+
+fn format_time_of_day(data: &UserData, nf: NumberFormat) -> String {
+    let hours = data.wpm_by_hour();
+    let top = hours.iter().map(|h| h.1).fold(0.0, f64::max);
+    let mut text = String::from("Average WPM by hour of day");
+    for (hour, wpm, tests) in hours {
+        let bar = "█".repeat(if top > 0.0 { (wpm / top * 20.0).round() as usize } else { 0 });
+        let marker = if wpm == top { " <- best" } else { "" };
+        text.push_str(&format!("\n{:02}:00  {:<20} {:>6} WPM ({} tests){}", hour, bar, nf.float(wpm, 1), nf.int(tests), marker));
+    }
+    text
+}
+
+// Makes whitespace readable in confusion listings
+fn key_name(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        '\n' => "enter".to_string(),
+        c => c.to_string(),
+    }
+}
+
+fn format_confusions(data: &UserData) -> String {
+    let confusions = data.top_confusions(15);
+    if confusions.is_empty() {
+        return "No mistakes recorded yet.".to_string();
+    }
+    let mut text = String::from("Most common confusions (wanted -> pressed)");
+    for (target, pressed, count) in confusions {
+        text.push_str(&format!("\n{:>5} -> {:<5} {} times", key_name(target), key_name(pressed), count));
+    }
+    text
+}
+
+// Markdown table of every letter seen, weakest first, for pasting into forum posts
+fn format_letter_report(data: &UserData) -> String {
+    let mut stats = data.letter_stats();
+    stats.retain(|s| !s.letter.is_whitespace());
+    stats.sort_by(|a, b| a.accuracy.total_cmp(&b.accuracy).then(a.wpm.total_cmp(&b.wpm)));
+
+    let mut text = format!(
+        "# Letter stats\n\n{} tests, average {:.1} WPM at {:.1}% accuracy.\n\n| Letter | Accuracy | WPM | Seen |\n|:------:|---------:|----:|-----:|\n",
+        data.total_tests(),
+        data.average_wpm().unwrap_or(0.0),
+        data.average_accuracy().unwrap_or(0.0),
+    );
+    for s in stats {
+        let letter = markdown_cell(&s.letter.to_string());
+        text.push_str(&format!("| {} | {:.1}% | {:.0} | {} |\n", letter, s.accuracy * 100.0, s.wpm, s.shown));
+    }
+
+    let confusions = data.top_confusions(20);
+    if !confusions.is_empty() {
+        text.push_str("\n## Common confusions\n\n| Wanted | Pressed | Times |\n|:------:|:-------:|------:|\n");
+        for (target, pressed, count) in confusions {
+            text.push_str(&format!("| {} | {} | {} |\n", markdown_cell(&key_name(target)), markdown_cell(&key_name(pressed)), count));
+        }
+    }
+    text
+}
+
+// Escape characters that would break a Markdown table
+fn markdown_cell(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '|' | '`' | '\\' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+fn history_menu(app: &AppState) -> Result<()> {
+    let history = &app.user_data.test_history;
+    if history.is_empty() {
+        gum_style("No saved results yet.")?;
+        wait_for_enter();
+        return Ok(());
+    }
+
+    loop {
+        let selection = gum_choose("History", &["Overview", "Time of Day", "Word Lengths", "Daily Challenges", "Confusions", "Latest vs Best", "Compare Two Runs", "WPM Histogram", "Export Letter Stats", "Back"])?;
+        match selection.as_str() {
+            "Overview" => {
+                gum_style(&format_overview(&app.user_data, &app.settings))?;
+                wait_for_enter();
+            }
+            "Confusions" => {
+                gum_style(&format_confusions(&app.user_data))?;
+                wait_for_enter();
+            }
+            "Time of Day" => {
+                gum_style(&format_time_of_day(&app.user_data, app.settings.number_format))?;
+                wait_for_enter();
+            }
+            "Daily Challenges" => {
+                gum_style(&format_daily_history(&app.user_data, app.settings.number_format))?;
+                wait_for_enter();
+            }
+            "Word Lengths" => {
+                gum_style(&format_word_lengths(&app.user_data, app.settings.number_format))?;
+                wait_for_enter();
+            }
+            "Latest vs Best" => {
+                let latest = &history[history.len() - 1];
+                let best = app.user_data.best_result().unwrap_or(latest);
+                compare_results(best, latest, app.settings.theme, app.settings.number_format)?;
+            }
+            "Compare Two Runs" => {
+                // Newest first, labels carry the index so we can map the selection back
+                let labels: Vec<String> = history.iter().enumerate().rev().map(|(i, r)| history_label(i, r, app.settings.number_format)).collect();
+                let opts_str: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+                let picked = gum_choose_many("Select two runs (Space to mark, Enter to confirm)", &opts_str, 2, None)?;
+                let mut indices: Vec<usize> = picked
+                    .iter()
+                    .filter_map(|l| l.trim_start_matches('#').split_whitespace().next()?.parse::<usize>().ok())
+                    .map(|n| n - 1)
+                    .filter(|&i| i < history.len())
+                    .collect();
+                indices.sort_unstable();
+                if let [older, newer] = indices[..] {
+                    compare_results(&history[older], &history[newer], app.settings.theme, app.settings.number_format)?;
+                }
+            }
+            "WPM Histogram" => show_wpm_histogram(history, app.settings.theme)?,
+            "Export Letter Stats" => {
+                let path = gum_input("Save Markdown report to", "letter-stats.md", "letter-stats.md")?;
+                if !path.is_empty() {
+                    match fs::write(&path, format_letter_report(&app.user_data)) {
+                        Ok(()) => gum_style(&format!("Letter stats written to {}", path))?,
+                        Err(e) => gum_error(&format!("Could not write {}: {}", path, e)),
+                    }
+                    wait_for_enter();
+                }
+            }
+            _ => break,
+        }
+    }
+    Ok(())
+}
+
+// Same words for everyone on a given day: the built-in list (not the user's, nor their
+// weights) with an RNG seeded from the date
+fn daily_text(day: NaiveDate, separator: char) -> String {
+    let mut rng = StdRng::seed_from_u64(day.num_days_from_ce() as u64);
+    let pool: Vec<&str> = DEFAULT_WORDS_STR.split_whitespace().collect();
+    let words: Vec<&str> = (0..DAILY_WORDS).filter_map(|_| pool.choose(&mut rng).copied()).collect();
+    words.join(&separator.to_string())
+}
+
+fn daily_challenge(app: &mut AppState) -> Result<Option<TestResult>> {
+    let today = Local::now().date_naive();
+    let best_today = app.user_data.test_history.iter().filter(|r| r.daily == Some(today)).map(|r| r.wpm).reduce(f64::max);
+    let mut intro = format!("Daily Challenge for {}\nStreak: {} days", today.format("%Y-%m-%d"), app.user_data.daily_streak(today));
+    if let Some(best) = best_today {
+        intro.push_str(&format!("\nToday's best: {} WPM", app.settings.number_format.float(best, 1)));
+    }
+    gum_style(&intro)?;
+    if gum_choose("Daily Challenge", &["Start", "Back"])? != "Start" {
+        return Ok(None);
+    }
+    app.prepared_text = Some(daily_text(today, app.settings.word_separator));
+    let mut res = run_test(app, TestMode::Words(DAILY_WORDS))?;
+    if let Some(res) = &mut res {
+        res.daily = Some(today);
+    }
+    Ok(res)
+}
 
-    let target_count = match mode {
-        TestMode::Words(n) => n,
-        TestMode::Time(_) | TestMode::Forever => 50,
+fn format_daily_history(data: &UserData, nf: NumberFormat) -> String {
+    let today = Local::now().date_naive();
+    let mut days: Vec<(NaiveDate, f64, f64)> = Vec::new(); // (day, best WPM, its accuracy)
+    for res in &data.test_history {
+        let Some(day) = res.daily else { continue };
+        match days.iter_mut().find(|d| d.0 == day) {
+            Some(entry) if res.wpm > entry.1 => *entry = (day, res.wpm, res.accuracy),
+            Some(_) => {}
+            None => days.push((day, res.wpm, res.accuracy)),
+        }
+    }
+    if days.is_empty() {
+        return "No Daily Challenges saved yet.".to_string();
+    }
+    days.sort_by_key(|d| std::cmp::Reverse(d.0));
+    let mut text = format!("Daily Challenges (streak: {} days)", data.daily_streak(today));
+    for (day, wpm, accuracy) in days.iter().take(14) {
+        text.push_str(&format!("\n{}  {:>6} WPM  {:>5}%", day.format("%Y-%m-%d"), nf.float(*wpm, 1), nf.float(*accuracy, 1)));
+    }
+    text
+}
+
+// Pangrams then common words: every letter at least twice, so the weighting has real
+// numbers to start from instead of a cold start
+const CALIBRATION_TEXT: &str = "the quick brown fox jumps over the lazy dog pack my box with five dozen liquor jugs \
+    how vexingly quick daft zebras jump and then there was time for people to work with all of them";
+
+// Seeds the letter stats; the run itself isn't kept in the history
+fn calibrate(app: &mut AppState) -> Result<Option<TestResult>> {
+    let sep = app.settings.word_separator.to_string();
+    let text = CALIBRATION_TEXT.split_whitespace().collect::<Vec<_>>().join(&sep);
+    let words = CALIBRATION_TEXT.split_whitespace().count();
+    app.prepared_text = Some(text);
+    let mut res = run_test(app, TestMode::Words(words))?;
+    if let Some(res) = &mut res {
+        res.invalid_reason = Some("calibration run, only the letter stats are kept".to_string());
+    }
+    Ok(res)
+}
+
+// Same kind of test as the least accurate recent run. Texts aren't saved, so it's the
+// mode that repeats, not the words.
+fn review_worst(app: &mut AppState) -> Result<Option<TestResult>> {
+    let Some(worst) = app.user_data.worst_recent(REVIEW_RECENT) else {
+        gum_style("No saved results yet.")?;
+        wait_for_enter();
+        return Ok(None);
     };
-    let mut target_text = app.get_weighted_words(target_count);
-    let mut input_text = String::new();
-    
-    let mut last_keystroke = Instant::now();
-    let mut is_started = false;
-    let mut real_start_time = Instant::now();
-    
-    let mut should_exit = false;
-    let mut completed = false;
-    let mut scroll_offset = 0;
+    let mode = worst.mode.unwrap_or(TestMode::Words(app.settings.default_words_limit));
+    let nf = app.settings.number_format;
+    gum_style(&format!(
+        "Worst of your last {} runs\n{}  {}\n{} WPM, {}% accuracy",
+        REVIEW_RECENT, worst.timestamp.format("%Y-%m-%d %H:%M"), mode.label(), nf.float(worst.wpm, 1), nf.float(worst.accuracy, 1)
+    ))?;
+    if gum_choose("Review Worst Run", &["Try Again", "Back"])? != "Try Again" {
+        return Ok(None);
+    }
+    run_test(app, mode)
+}
 
-    while !should_exit && !completed {
-        let elapsed = if is_started { real_start_time.elapsed() } else { Duration::from_secs(0) };
-        let wpm = if elapsed.as_secs_f64() > 0.0 {
-             (input_text.len() as f64 / 5.0) / (elapsed.as_secs_f64() / 60.0)
-        } else {
-            0.0
-        };
+// Words test strongly favouring `letters` for one run, in place of any manual focus
+fn focus_drill(app: &mut AppState, letters: Vec<char>, words: usize) -> Result<Option<TestResult>> {
+    // Selection has to be adaptive for the boost to mean anything
+    let saved = app.settings.adaptive_selection;
+    app.settings.adaptive_selection = true;
+    let manual = std::mem::replace(&mut app.focus_letters, letters);
+    let res = run_test(app, TestMode::Words(words));
+    app.focus_letters = manual;
+    app.settings.adaptive_selection = saved;
+    res
+}
 
-        // Check if Time Mode is finished
-        if let TestMode::Time(limit) = mode {
-            if is_started && elapsed.as_secs() >= limit {
-                completed = true;
-                break;
+// a-z with each letter's accuracy, so weak ones are easy to spot while picking
+fn pick_letters(app: &AppState) -> Result<Vec<char>> {
+    let stats = app.user_data.letter_stats();
+    let nf = app.settings.number_format;
+    let options: Vec<String> = ('a'..='z')
+        .map(|c| match stats.iter().find(|s| s.letter == c) {
+            Some(s) => format!("{}  {}%  {} WPM", c, nf.float(s.accuracy * 100.0, 0), nf.float(s.wpm, 0)),
+            None => format!("{}  no data", c),
+        })
+        .collect();
+    let options: Vec<&str> = options.iter().map(|o| o.as_str()).collect();
+    let picked = gum_choose_many("Letters for this drill (space to select, enter to confirm)", &options, options.len(), None)?;
+    Ok(picked.iter().filter_map(|o| o.chars().next()).collect())
+}
+
+// Create, run and delete saved drills; a run's result goes back to the main loop
+fn drills_menu(app: &mut AppState) -> Result<Option<TestResult>> {
+    loop {
+        let labels: Vec<String> = app
+            .drills
+            .iter()
+            .map(|d| format!("Run: {} ({}, {} words)", d.name, d.letters.iter().collect::<String>(), d.words))
+            .collect();
+        let mut options: Vec<&str> = labels.iter().map(|l| l.as_str()).collect();
+        options.extend(["New Drill", "Delete Drill", "Back"]);
+        let selection = gum_choose("Saved Drills", &options)?;
+
+        if let Some(i) = labels.iter().position(|l| *l == selection) {
+            let drill = app.drills[i].clone();
+            return focus_drill(app, drill.letters, drill.words);
+        }
+        match selection.as_str() {
+            "New Drill" => {
+                let name = gum_input("Drill name", "home row", "")?;
+                if name.trim().is_empty() {
+                    continue;
+                }
+                let letters = pick_letters(app)?;
+                if letters.is_empty() {
+                    continue;
+                }
+                let Some(words) = gum_input_number("Words per run", "20", PRACTICE_WORDS, 5..=500)? else {
+                    continue;
+                };
+                let drill = SavedDrill { name: name.trim().to_string(), letters, words };
+                // Same name: the new drill replaces the old one
+                match app.drills.iter_mut().find(|d| d.name == drill.name) {
+                    Some(existing) => *existing = drill,
+                    None => app.drills.push(drill),
+                }
+                if let Err(e) = app.save_drills() {
+                    gum_error(&format!("Save failed: {:#}", e));
+                }
+            }
+            "Delete Drill" => {
+                let mut names: Vec<&str> = app.drills.iter().map(|d| d.name.as_str()).collect();
+                names.push("Back");
+                let name = gum_choose("Delete which drill?", &names)?;
+                if name == "Back" || name.is_empty() || !gum_confirm(&format!("Delete '{}'?", name)) {
+                    continue;
+                }
+                app.drills.retain(|d| d.name != name);
+                if let Err(e) = app.save_drills() {
+                    gum_error(&format!("Save failed: {:#}", e));
+                }
             }
+            _ => return Ok(None),
         }
+    }
+}
+
+// Lock a text, practise it, see how its runs improve; a practice result goes back to the main loop
+fn locked_text_menu(app: &mut AppState) -> Result<Option<TestResult>> {
+    loop {
+        let Some(locked) = app.locked_text.clone() else {
+            if gum_choose("No text locked yet", &["Lock New Text", "Back"])? != "Lock New Text" {
+                return Ok(None);
+            }
+            let Some(text) = preview_text(app, app.settings.default_words_limit)? else {
+                return Ok(None);
+            };
+            let words = text.split(app.settings.word_separator).map(|w| w.to_string()).collect();
+            app.locked_text = Some(LockedText { words, locked_at: Local::now(), runs: Vec::new() });
+            if let Err(e) = app.save_locked() {
+                gum_error(&format!("Save failed: {:#}", e));
+            }
+            continue;
+        };
 
-        // Buffer management for continuous modes
-        if matches!(mode, TestMode::Time(_) | TestMode::Forever) {
-            if input_text.len() + 50 > target_text.len() {
-                let more = app.get_weighted_words(20);
-                target_text.push(' ');
-                target_text.push_str(&more);
+        let header = format!("Locked text: {} words, {} runs since {}", locked.words.len(), locked.runs.len(), locked.locked_at.format("%Y-%m-%d"));
+        match gum_choose(&header, &["Practice", "Improvement", "Unlock", "Back"])?.as_str() {
+            "Practice" => {
+                app.prepared_text = Some(locked.words.join(&app.settings.word_separator.to_string()));
+                let res = run_test(app, TestMode::Words(locked.words.len()))?;
+                if let (Some(res), Some(locked)) = (&res, &mut app.locked_text) {
+                    if res.invalid_reason.is_none() {
+                        locked.runs.push(LockedRun { timestamp: res.timestamp, wpm: res.wpm, accuracy: res.accuracy });
+                        if let Err(e) = app.save_locked() {
+                            gum_error(&format!("Save failed: {:#}", e));
+                        }
+                    }
+                }
+                return Ok(res);
+            }
+            "Improvement" => {
+                gum_style(&format_locked_progress(&locked, app.settings.number_format))?;
+                wait_for_enter();
             }
+            "Unlock" => {
+                if gum_confirm(&format!("Unlock this text and forget its {} runs?", locked.runs.len())) {
+                    app.locked_text = None;
+                    if let Err(e) = app.save_locked() {
+                        gum_error(&format!("Save failed: {:#}", e));
+                    }
+                }
+            }
+            _ => return Ok(None),
         }
+    }
+}
 
-        // Draw UI
-        terminal.draw(|f| {
-            let layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Fill(1),
-                    Constraint::Length(12),
-                    Constraint::Min(1),
-                    Constraint::Length(1),
-                ])
-                .split(f.size());
+const LOCKED_BAR_WIDTH: usize = 30;
+
+// Runs oldest first, each with a bar scaled to the best WPM, then first vs best vs latest
+fn format_locked_progress(locked: &LockedText, nf: NumberFormat) -> String {
+    let preview: Vec<&str> = locked.words.iter().take(8).map(|w| w.as_str()).collect();
+    let mut text = format!("\"{}{}\"\n", preview.join(" "), if locked.words.len() > 8 { " ..." } else { "" });
+    let (Some(first), Some(latest)) = (locked.runs.first(), locked.runs.last()) else {
+        text.push_str("\nNo runs yet.");
+        return text;
+    };
+    let best = locked.runs.iter().map(|r| r.wpm).fold(0.0, f64::max);
+    for (i, run) in locked.runs.iter().enumerate() {
+        let bar = if best > 0.0 { (run.wpm / best * LOCKED_BAR_WIDTH as f64).round() as usize } else { 0 };
+        text.push_str(&format!(
+            "\n#{:<3} {}  {:>6} WPM  {:>5}%  {}",
+            i + 1, run.timestamp.format("%m-%d"), nf.float(run.wpm, 1), nf.float(run.accuracy, 1), "█".repeat(bar)
+        ));
+    }
+    text.push_str(&format!(
+        "\n\nFirst {} / Best {} / Latest {} WPM ({}{})",
+        nf.float(first.wpm, 1), nf.float(best, 1), nf.float(latest.wpm, 1),
+        if latest.wpm >= first.wpm { "+" } else { "" }, nf.float(latest.wpm - first.wpm, 1)
+    ));
+    text
+}
 
-            // Header Area
-            let mode_str = match mode {
-                TestMode::Time(t) => format!("Time Mode: {}s", t),
-                TestMode::Words(w) => format!("Words Mode: {}", w),
-                TestMode::Forever => "Forever Mode".to_string(),
+// Mode -> setting -> cycles Default (the global value) / On / Off
+fn mode_overrides_menu(settings: &mut Settings) -> Result<()> {
+    loop {
+        let kind = gum_choose("Overrides for which mode?", &[&MODE_KINDS[..], &["Back"]].concat())?;
+        if !MODE_KINDS.contains(&kind.as_str()) {
+            return Ok(());
+        }
+        loop {
+            let mut overrides = settings.mode_overrides.get(&kind).cloned().unwrap_or_default();
+            let mut options: Vec<String> = OVERRIDE_FIELDS
+                .iter()
+                .map(|field| match *(field.slot)(&mut overrides) {
+                    Some(value) => format!("{}: {}", field.label, if value { "On" } else { "Off" }),
+                    None => format!("{}: Default ({})", field.label, if *(field.global)(settings) { "On" } else { "Off" }),
+                })
+                .collect();
+            options.push("Back".to_string());
+            let options: Vec<&str> = options.iter().map(|o| o.as_str()).collect();
+            let selection = gum_choose(&format!("{} Mode overrides", kind), &options)?;
+            let Some(field) = OVERRIDE_FIELDS.iter().find(|f| selection.starts_with(&format!("{}:", f.label))) else {
+                break;
             };
-            
-            let status = if is_started {
-                match mode {
-                    TestMode::Time(limit) => format!("{} | Time Left: {:.0}s | WPM: {:.0}", mode_str, (limit as f64 - elapsed.as_secs_f64()).max(0.0), wpm),
-                    _ => format!("{} | Time: {:.0}s | WPM: {:.0}", mode_str, elapsed.as_secs_f64(), wpm),
-                }
+            let slot = (field.slot)(&mut overrides);
+            *slot = match *slot {
+                None => Some(true),
+                Some(true) => Some(false),
+                Some(false) => None,
+            };
+            settings.mode_overrides.insert(kind.clone(), overrides);
+        }
+    }
+}
+
+// --- Native Settings Editor ---
+
+// One row of the arrow-key editor. `adjust` gets +1/-1 from Right/Left; toggles and
+// enums ignore the sign.
+struct SettingItem {
+    label: &'static str,
+    value: fn(&Settings) -> String,
+    adjust: fn(&mut Settings, i64),
+}
+
+fn on_off(b: bool) -> String {
+    if b { "On" } else { "Off" }.to_string()
+}
+
+fn grace_label(ms: u64) -> String {
+    if ms == 0 { "Off (space required)".to_string() } else { format!("{}ms", ms) }
+}
+
+// Bounds shared by the gum settings menu and the quick editor, so a value typed into one
+// is never clamped by the other
+const TIME_LIMIT_RANGE: RangeInclusive<u64> = 1..=3600;
+const WORDS_LIMIT_RANGE: RangeInclusive<usize> = 1..=1000;
+const CHARS_LIMIT_RANGE: RangeInclusive<usize> = 1..=10000;
+const PRESSURE_START_RANGE: RangeInclusive<f64> = 1.0..=30.0;
+const PRESSURE_DECAY_RANGE: RangeInclusive<f64> = 0.5..=1.0;
+const GOAL_RANGE: RangeInclusive<f64> = 0.0..=300.0;
+const ACCURACY_FLOOR_RANGE: RangeInclusive<f64> = 0.0..=100.0;
+const REMINDER_DAYS_RANGE: RangeInclusive<u32> = 0..=365;
+const REPEAT_ATTEMPTS_RANGE: RangeInclusive<u32> = 1..=20;
+const BACKSPACE_PENALTY_RANGE: RangeInclusive<f64> = 0.0..=10.0;
+const COLUMNS_RANGE: RangeInclusive<u8> = 1..=2;
+const SCROLL_MARGIN_RANGE: RangeInclusive<usize> = 0..=10;
+const EXPONENT_RANGE: RangeInclusive<f64> = 0.0..=5.0;
+const AUTOSAVE_RANGE: RangeInclusive<u64> = 0..=3600;
+const FINAL_SPACE_GRACE_RANGE: RangeInclusive<u64> = 0..=5000;
+const RESULTS_DELAY_RANGE: RangeInclusive<u64> = 0..=5000;
+
+fn step<T: Copy + TryInto<i64> + TryFrom<i64>>(value: T, delta: i64, range: &RangeInclusive<T>) -> T {
+    let wide = |v: T| v.try_into().unwrap_or(i64::MAX);
+    let next = wide(value).saturating_add(delta).clamp(wide(*range.start()), wide(*range.end()));
+    T::try_from(next).unwrap_or(*range.start())
+}
+
+fn step_f64(value: f64, delta: f64, range: &RangeInclusive<f64>) -> f64 {
+    (((value + delta) * 100.0).round() / 100.0).clamp(*range.start(), *range.end())
+}
+
+fn settings_items() -> Vec<SettingItem> {
+    vec![
+        SettingItem { label: "Forgive Errors", value: |s| on_off(s.forgive_errors), adjust: |s, _| s.forgive_errors = !s.forgive_errors },
+        SettingItem { label: "Failed Word Scoring", value: |s| on_off(s.fail_words), adjust: |s, _| s.fail_words = !s.fail_words },
+        SettingItem { label: "Case Slips", value: |s| if s.case_slips { "Separate" } else { "Normal Errors" }.to_string(), adjust: |s, _| s.case_slips = !s.case_slips },
+        SettingItem { label: "Enforce Correction", value: |s| on_off(s.enforce_correction), adjust: |s, _| s.enforce_correction = !s.enforce_correction },
+        SettingItem { label: "Default Time", value: |s| format!("{}s", s.default_time_limit), adjust: |s, d| s.default_time_limit = step(s.default_time_limit, d * 5, &TIME_LIMIT_RANGE) },
+        SettingItem { label: "Default Words", value: |s| s.default_words_limit.to_string(), adjust: |s, d| s.default_words_limit = step(s.default_words_limit, d * 5, &WORDS_LIMIT_RANGE) },
+        SettingItem { label: "Default Chars", value: |s| s.default_chars_limit.to_string(), adjust: |s, d| s.default_chars_limit = step(s.default_chars_limit, d * 50, &CHARS_LIMIT_RANGE) },
+        SettingItem { label: "Pressure Start", value: |s| format!("{}s", s.pressure_start_budget), adjust: |s, d| s.pressure_start_budget = step_f64(s.pressure_start_budget, d as f64 * 0.5, &PRESSURE_START_RANGE) },
+        SettingItem { label: "Pressure Decay", value: |s| format!("{:.2}", s.pressure_decay), adjust: |s, d| s.pressure_decay = step_f64(s.pressure_decay, d as f64 * 0.01, &PRESSURE_DECAY_RANGE) },
+        SettingItem { label: "Live WPM", value: |s| on_off(s.show_wpm_live), adjust: |s, _| s.show_wpm_live = !s.show_wpm_live },
+        SettingItem { label: "Blind Mode", value: |s| on_off(s.blind_mode), adjust: |s, _| s.blind_mode = !s.blind_mode },
+        SettingItem { label: "Goal", value: |s| format!("{} WPM", s.goal_wpm), adjust: |s, d| s.goal_wpm = step_f64(s.goal_wpm, d as f64 * 5.0, &GOAL_RANGE) },
+        SettingItem { label: "Accuracy Floor", value: |s| format!("{}%", s.accuracy_floor), adjust: |s, d| s.accuracy_floor = step_f64(s.accuracy_floor, d as f64 * 5.0, &ACCURACY_FLOOR_RANGE) },
+        SettingItem { label: "Below Floor", value: |s| if s.accuracy_floor_ends_test { "End Test" } else { "Warn" }.to_string(), adjust: |s, _| s.accuracy_floor_ends_test = !s.accuracy_floor_ends_test },
+        SettingItem { label: "Theme", value: |s| format!("{:?}", s.theme), adjust: |s, _| s.theme = s.theme.next() },
+        SettingItem { label: "Alternate Screen", value: |s| on_off(s.use_alt_screen), adjust: |s, _| s.use_alt_screen = !s.use_alt_screen },
+        SettingItem { label: "Large Text", value: |s| on_off(s.large_text), adjust: |s, _| s.large_text = !s.large_text },
+        SettingItem { label: "Results Key Delay", value: |s| format!("{}ms", s.results_input_delay_ms), adjust: |s, d| s.results_input_delay_ms = step(s.results_input_delay_ms, d * 100, &RESULTS_DELAY_RANGE) },
+        SettingItem { label: "Final Space Grace", value: |s| grace_label(s.final_space_grace_ms), adjust: |s, d| s.final_space_grace_ms = step(s.final_space_grace_ms, d * 100, &FINAL_SPACE_GRACE_RANGE) },
+        SettingItem { label: "Autosave", value: |s| if s.autosave_secs == 0 { "Off".to_string() } else { format!("every {}s", s.autosave_secs) }, adjust: |s, d| s.autosave_secs = step(s.autosave_secs, d * 15, &AUTOSAVE_RANGE) },
+        SettingItem { label: "Practice Reminder", value: |s| format!("{} days", s.reminder_days), adjust: |s, d| s.reminder_days = step(s.reminder_days, d, &REMINDER_DAYS_RANGE) },
+        SettingItem { label: "Collapse Spaces in Lists", value: |s| on_off(s.collapse_spaces), adjust: |s, _| s.collapse_spaces = !s.collapse_spaces },
+        SettingItem { label: "Number Format", value: |s| format!("{:?} ({})", s.number_format, s.number_format.float(12345.6, 1)), adjust: |s, _| s.number_format = s.number_format.next() },
+        SettingItem { label: "Show Whitespace", value: |s| on_off(s.show_whitespace), adjust: |s, _| s.show_whitespace = !s.show_whitespace },
+        SettingItem { label: "Enter as Space", value: |s| on_off(s.enter_as_space), adjust: |s, _| s.enter_as_space = !s.enter_as_space },
+        SettingItem { label: "Auto Space", value: |s| on_off(s.auto_space), adjust: |s, _| s.auto_space = !s.auto_space },
+        SettingItem { label: "Clean Words Only", value: |s| on_off(s.count_clean_words), adjust: |s, _| s.count_clean_words = !s.count_clean_words },
+        SettingItem { label: "Distractions", value: |s| on_off(s.distractions), adjust: |s, _| s.distractions = !s.distractions },
+        SettingItem { label: "Error Ticker", value: |s| on_off(s.show_error_ticker), adjust: |s, _| s.show_error_ticker = !s.show_error_ticker },
+        SettingItem { label: "Minimap", value: |s| on_off(s.show_minimap), adjust: |s, _| s.show_minimap = !s.show_minimap },
+        SettingItem { label: "Length Ramp", value: |s| on_off(s.auto_length_ramp), adjust: |s, _| s.auto_length_ramp = !s.auto_length_ramp },
+        SettingItem { label: "Preview Words Text", value: |s| on_off(s.preview_words), adjust: |s, _| s.preview_words = !s.preview_words },
+        SettingItem {
+            label: "Word Separator",
+            value: |s| separator_name(s.word_separator),
+            adjust: |s, d| {
+                // Custom characters can still be entered through the gum menu
+                const CHOICES: [char; 3] = [' ', '\n', ','];
+                let idx = CHOICES.iter().position(|&c| c == s.word_separator).unwrap_or(0) as i64;
+                s.word_separator = CHOICES[(idx + d).rem_euclid(CHOICES.len() as i64) as usize];
+            },
+        },
+        SettingItem { label: "Adaptive Words", value: |s| on_off(s.adaptive_selection), adjust: |s, _| s.adaptive_selection = !s.adaptive_selection },
+        SettingItem { label: "Non-adaptive Order", value: |s| if s.sequential_order { "Sequential" } else { "Shuffled" }.to_string(), adjust: |s, _| s.sequential_order = !s.sequential_order },
+        SettingItem { label: "Sequential List Order", value: |s| format!("{:?}", s.list_order), adjust: |s, _| s.list_order = s.list_order.next() },
+        SettingItem { label: "Repeat Missed Words", value: |s| on_off(s.repeat_until_clean), adjust: |s, _| s.repeat_until_clean = !s.repeat_until_clean },
+        SettingItem { label: "Repeat Attempts", value: |s| s.repeat_max_attempts.to_string(), adjust: |s, d| s.repeat_max_attempts = step(s.repeat_max_attempts, d, &REPEAT_ATTEMPTS_RANGE) },
+        SettingItem { label: "Backspace Penalty", value: |s| format!("{} chars", s.backspace_penalty), adjust: |s, d| s.backspace_penalty = step_f64(s.backspace_penalty, d as f64 * 0.5, &BACKSPACE_PENALTY_RANGE) },
+        SettingItem { label: "Paste Handling", value: |s| format!("{:?}", s.paste_policy), adjust: |s, _| s.paste_policy = s.paste_policy.next() },
+        SettingItem { label: "Words Progress", value: |s| format!("{:?}", s.progress_format), adjust: |s, _| s.progress_format = s.progress_format.next() },
+        SettingItem { label: "Text Columns", value: |s| s.columns.to_string(), adjust: |s, d| s.columns = step(s.columns, d, &COLUMNS_RANGE) },
+        SettingItem { label: "Scrolling", value: |s| format!("{:?}", s.scroll_policy), adjust: |s, _| s.scroll_policy = s.scroll_policy.next() },
+        SettingItem { label: "Scroll Margin", value: |s| format!("{} lines", s.scroll_margin), adjust: |s, d| s.scroll_margin = step(s.scroll_margin, d, &SCROLL_MARGIN_RANGE) },
+        SettingItem { label: "Warm-up Gauge", value: |s| on_off(s.show_warmup), adjust: |s, _| s.show_warmup = !s.show_warmup },
+        SettingItem { label: "Finish Animation", value: |s| on_off(s.completion_animation), adjust: |s, _| s.completion_animation = !s.completion_animation },
+        SettingItem { label: "Finish Sound", value: |s| on_off(s.completion_sound), adjust: |s, _| s.completion_sound = !s.completion_sound },
+        SettingItem { label: "Ephemeral Mode", value: |s| on_off(s.ephemeral), adjust: |s, _| s.ephemeral = !s.ephemeral },
+        SettingItem { label: "Accuracy Weight", value: |s| format!("{:.2}", s.accuracy_exponent), adjust: |s, d| s.accuracy_exponent = step_f64(s.accuracy_exponent, d as f64 * 0.1, &EXPONENT_RANGE) },
+        SettingItem { label: "Frequency Weight", value: |s| format!("{:.2}", s.frequency_exponent), adjust: |s, d| s.frequency_exponent = step_f64(s.frequency_exponent, d as f64 * 0.1, &EXPONENT_RANGE) },
+        SettingItem { label: "Speed Weight", value: |s| format!("{:.2}", s.speed_exponent), adjust: |s, d| s.speed_exponent = step_f64(s.speed_exponent, d as f64 * 0.1, &EXPONENT_RANGE) },
+    ]
+}
+
+// A short typed sample drawn with the current display settings
+fn settings_preview(settings: &Settings) -> Line<'static> {
+    let target = ["the", "quick", "brown", "fox"].join(&settings.word_separator.to_string());
+    // One deliberate mistake so the error colouring shows up too
+    let typed: Vec<char> = target.chars().take(12).enumerate().map(|(i, c)| if i == 5 { 'x' } else { c }).collect();
+    let spans: Vec<Span> = target
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let style = if settings.blind_mode && i < typed.len() {
+                Style::default().fg(Color::White)
             } else {
-                format!("{} | Press any key to start typing...", mode_str)
+                char_style(c, typed.get(i).copied(), i == typed.len())
             };
+            Span::styled(display_glyph(c, settings.show_whitespace).to_string(), style)
+        })
+        .collect();
+    Line::from(spans)
+}
+
+// Up/Down to move, Left/Right to change a value, Space/Enter to toggle, Esc to save and leave
+fn settings_editor(app: &mut AppState) -> Result<()> {
+    let items = settings_items();
+    let mut state = TableState::default().with_selected(Some(0));
+    let quit = app.settings.keybindings.resolve().map_err(anyhow::Error::msg)?.quit;
+    let mut terminal = TerminalGuard::fullscreen()?;
+
+    loop {
+        terminal.draw(|f| {
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(5), Constraint::Length(3), Constraint::Length(1)])
+                .split(f.size());
 
             f.render_widget(
-                Paragraph::new(status).bg(Color::Rgb(46, 2, 91)).bold().alignment(Alignment::Center).block(Block::default().borders(Borders::BOTTOM)),
+                Paragraph::new("Settings").style(app.settings.theme.header()).alignment(Alignment::Center),
                 layout[0]
             );
 
-            // Typing Text Area
-            let width = layout[1].width as usize;
-            let visible_lines = layout[1].height as usize;
-            let cursor_row = input_text.len() / width;
-            
-            // Auto scroll
-            if cursor_row > scroll_offset + visible_lines / 2 {
-                scroll_offset = cursor_row - visible_lines / 2;
+            let rows: Vec<Row> = items
+                .iter()
+                .map(|item| Row::new(vec![Cell::from(item.label), Cell::from((item.value)(&app.settings))]))
+                .collect();
+            f.render_stateful_widget(
+                Table::new(rows, [Constraint::Length(22), Constraint::Min(10)])
+                    .highlight_style(Style::default().fg(Color::Black).bg(Color::Rgb(7, 206, 65)))
+                    .block(Block::default().borders(Borders::ALL).padding(ratatui::widgets::Padding::new(1,1,0,0))),
+                layout[1],
+                &mut state
+            );
+
+            f.render_widget(
+                Paragraph::new(settings_preview(&app.settings)).block(Block::default().borders(Borders::ALL).title("Preview")),
+                layout[2]
+            );
+            f.render_widget(
+                Paragraph::new(format!("↑↓: Select | ←→: Adjust | Space: Toggle | {}: Save & Back", app.settings.keybindings.quit.to_uppercase())).alignment(Alignment::Center).style(app.settings.theme.footer()),
+                layout[3]
+            );
+        })?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
             }
-            
-            let mut spans = Vec::new();
-            let start_char_idx = scroll_offset * width;
-            
-            if start_char_idx < target_text.len() {
-                let mut current_line = vec![];
-                let visible_text: Vec<(usize, char)> = target_text
-                    .char_indices()
-                    .skip(start_char_idx)
-                    .take(visible_lines * width)
-                    .collect();
+            let selected = state.selected().unwrap_or(0);
+            match key.code {
+                _ if quit.matches(&key) => break,
+                KeyCode::Up => state.select(Some(selected.saturating_sub(1))),
+                KeyCode::Down => state.select(Some((selected + 1).min(items.len() - 1))),
+                KeyCode::Left => (items[selected].adjust)(&mut app.settings, -1),
+                KeyCode::Right | KeyCode::Char(' ') | KeyCode::Enter => (items[selected].adjust)(&mut app.settings, 1),
+                _ => {}
+            }
+        }
+    }
 
-                let mut current_width = 0;
+    drop(terminal);
+    save_or_warn(app);
+    Ok(())
+}
 
-                for (absolute_idx, c) in visible_text {
-                    let style = if absolute_idx < input_text.len() {
-                        let inputted = input_text.chars().nth(absolute_idx).unwrap();
-                        if inputted == c {
-                            Style::default().fg(Color::Green)
-                        } else {
-                            Style::default().fg(Color::Red).add_modifier(Modifier::UNDERLINED)
-                        }
-                    } else if absolute_idx == input_text.len() {
-                        Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED | Modifier::BOLD)
-                    } else {
-                        Style::default().fg(Color::Gray)
-                    };
+fn separator_name(sep: char) -> String {
+    match sep {
+        ' ' => "space".to_string(),
+        '\n' => "newline".to_string(),
+        c => c.to_string(),
+    }
+}
 
-                    current_line.push(Span::styled(c.to_string(), style));
-                    current_width += 1;
+fn settings_menu(app: &mut AppState) -> Result<()> {
+    // What's shown here is what tests use from now on
+    app.session_forgive = None;
+    loop {
+        // Clone simple Copy types to avoid borrow issues
+        let options = [
+            "Quick Editor (arrow keys)".to_string(),
+            "Per-Mode Overrides".to_string(),
+            format!("Forgive Errors: {}", if app.settings.forgive_errors { "On" } else { "Off" }),
+            format!("Enforce Correction: {}", if app.settings.enforce_correction { "On" } else { "Off" }),
+            format!("Failed Word Scoring: {}", if app.settings.fail_words { "On" } else { "Off" }),
+            format!("Case Slips: {}", if app.settings.case_slips { "Separate" } else { "Normal Errors" }),
+            format!("Default Time: {}s", app.settings.default_time_limit),
+            format!("Default Words: {}", app.settings.default_words_limit),
+            format!("Default Chars: {}", app.settings.default_chars_limit),
+            format!("Pressure Start: {}s", app.settings.pressure_start_budget),
+            format!("Pressure Decay: {}", app.settings.pressure_decay),
+            format!("Live WPM: {}", if app.settings.show_wpm_live { "On" } else { "Off" }),
+            format!("Blind Mode: {}", if app.settings.blind_mode { "On" } else { "Off" }),
+            format!("Large Text: {}", if app.settings.large_text { "On" } else { "Off" }),
+            format!("Alternate Screen: {}", if app.settings.use_alt_screen { "On" } else { "Off (inline)" }),
+            format!("Theme: {:?}", app.settings.theme),
+            format!("Benchmarks: {}", app.settings.benchmark_wpm.map(|t| t.to_string()).join("/")),
+            format!("Goal: {}", if app.settings.goal_wpm > 0.0 { format!("{} WPM", app.settings.goal_wpm) } else { "None".to_string() }),
+            format!("Accuracy Floor: {}", if app.settings.accuracy_floor > 0.0 { format!("{}%", app.settings.accuracy_floor) } else { "Off".to_string() }),
+            format!("Below Floor: {}", if app.settings.accuracy_floor_ends_test { "End Test" } else { "Warn" }),
+            format!("Practice Reminder: {}", if app.settings.reminder_days == 0 { "Off".to_string() } else { format!("after {} days", app.settings.reminder_days) }),
+            format!("Enter as Space: {}", if app.settings.enter_as_space { "On" } else { "Off" }),
+            format!("Adaptive Words: {}", if app.settings.adaptive_selection { "On" } else { "Off (uniform)" }),
+            format!("Non-adaptive Order: {}", if app.settings.sequential_order { "Sequential" } else { "Shuffled" }),
+            format!("Sequential List Order: {:?}", app.settings.list_order),
+            format!("Auto Space: {}", if app.settings.auto_space { "On" } else { "Off" }),
+            format!("Words Count: {}", if app.settings.count_clean_words { "Clean Words Only" } else { "All Words" }),
+            format!("Distractions: {}", if app.settings.distractions { "On" } else { "Off" }),
+            format!("Minimap: {}", if app.settings.show_minimap { "On" } else { "Off" }),
+            format!("Error Ticker: {}", if app.settings.show_error_ticker { "On" } else { "Off" }),
+            format!("Length Ramp: {}", if app.settings.auto_length_ramp { format!("On (words of {}+ letters now)", app.ramp_min_length()) } else { "Off".to_string() }),
+            format!("Pinned Words: {}", if app.settings.pinned_words.is_empty() { "None".to_string() } else { app.settings.pinned_words.join(", ") }),
+            format!("Preview Words Text: {}", if app.settings.preview_words { "On" } else { "Off" }),
+            format!("Word Separator: {}", separator_name(app.settings.word_separator)),
+            format!("Word Lists: {}", if app.settings.word_lists.is_empty() { "Default".to_string() } else { app.settings.word_lists.join(", ") }),
+            "Import Monkeytype List".to_string(),
+            format!("Backspace Penalty: {} chars", app.settings.backspace_penalty),
+            format!("Paste Handling: {:?}", app.settings.paste_policy),
+            format!("Warm-up Gauge: {}", if app.settings.show_warmup { "On" } else { "Off" }),
+            format!("Repeat Missed Words: {}", if app.settings.repeat_until_clean { "On" } else { "Off" }),
+            format!("Repeat Attempts: {}", app.settings.repeat_max_attempts),
+            format!("Min Terminal Size: {}x{}", app.settings.min_terminal_size.0, app.settings.min_terminal_size.1),
+            format!("Text Columns: {}", app.settings.columns),
+            format!("Words Progress: {:?}", app.settings.progress_format),
+            format!("Scrolling: {:?}", app.settings.scroll_policy),
+            format!("Scroll Margin: {} lines", app.settings.scroll_margin),
+            format!("Collapse Spaces in Lists: {}", if app.settings.collapse_spaces { "On" } else { "Off" }),
+            format!("Number Format: {:?} ({})", app.settings.number_format, app.settings.number_format.float(12345.6, 1)),
+            format!("Show Whitespace: {}", if app.settings.show_whitespace { "On" } else { "Off" }),
+            format!("Ephemeral Mode: {}", if app.settings.ephemeral { "On" } else { "Off" }),
+            format!("Results Key Delay: {}ms", app.settings.results_input_delay_ms),
+            format!("Final Space Grace: {}", grace_label(app.settings.final_space_grace_ms)),
+            format!("Autosave: {}", if app.settings.autosave_secs == 0 { "Off".to_string() } else { format!("every {}s", app.settings.autosave_secs) }),
+            format!("Finish Animation: {}", if app.settings.completion_animation { "On" } else { "Off" }),
+            format!("Finish Sound: {}", if app.settings.completion_sound { "On" } else { "Off" }),
+            "Advanced...".to_string(),
+            "Reset History".to_string(),
+            "Back".to_string()
+        ];
+        
+        let opts_str: Vec<&str> = options.iter().map(|s| s.as_str()).collect();
+        let selection = gum_choose("Settings", &opts_str)?;
 
-                    if current_width >= width {
-                        spans.push(Line::from(current_line));
-                        current_line = vec![];
-                        current_width = 0;
+        if selection.starts_with("Back") {
+            break;
+        } else if selection.starts_with("Quick Editor") {
+            let (order, collapse) = (app.settings.list_order, app.settings.collapse_spaces);
+            settings_editor(app)?;
+            if app.settings.collapse_spaces != collapse {
+                for err in &app.reload_words() {
+                    gum_error(err);
+                }
+            } else if app.settings.list_order != order {
+                app.ordered_list = None;
+                app.sequential_cursor = 0;
+            }
+        } else if selection.starts_with("Forgive") {
+            app.settings.forgive_errors = !app.settings.forgive_errors;
+        } else if selection.starts_with("Failed Word Scoring") {
+            app.settings.fail_words = !app.settings.fail_words;
+        } else if selection.starts_with("Case Slips") {
+            app.settings.case_slips = !app.settings.case_slips;
+        } else if selection.starts_with("Enforce Correction") {
+            app.settings.enforce_correction = !app.settings.enforce_correction;
+        } else if selection.starts_with("Live WPM") {
+            app.settings.show_wpm_live = !app.settings.show_wpm_live;
+        } else if selection.starts_with("Blind Mode") {
+            app.settings.blind_mode = !app.settings.blind_mode;
+        } else if selection.starts_with("Repeat Missed Words") {
+            app.settings.repeat_until_clean = !app.settings.repeat_until_clean;
+        } else if selection.starts_with("Repeat Attempts") {
+            if let Some(n) = gum_input_number("Max repeats of a missed word", "3", app.settings.repeat_max_attempts, REPEAT_ATTEMPTS_RANGE)? {
+                app.settings.repeat_max_attempts = n;
+            }
+        } else if selection.starts_with("Scrolling") {
+            app.settings.scroll_policy = app.settings.scroll_policy.next();
+        } else if selection.starts_with("Scroll Margin") {
+            if let Some(n) = gum_input_number("Lines kept below the cursor before scrolling", "2", app.settings.scroll_margin, SCROLL_MARGIN_RANGE)? {
+                app.settings.scroll_margin = n;
+            }
+        } else if selection.starts_with("Min Terminal Size") {
+            let (w, h) = app.settings.min_terminal_size;
+            let mut value = format!("{}x{}", w, h);
+            loop {
+                let val = gum_input("Minimum terminal size (columns x rows)", "60x20", &value)?;
+                if val.is_empty() {
+                    break;
+                }
+                let parsed = val.split_once('x').map(|(w, h)| (w.trim().parse::<u16>(), h.trim().parse::<u16>()));
+                if let Some((Ok(w), Ok(h))) = parsed {
+                    if w > 0 && h > 0 {
+                        app.settings.min_terminal_size = (w, h);
+                        break;
+                    }
+                }
+                gum_error(&format!("'{}' isn't a size like 60x20", val));
+                value = val;
+            }
+        } else if selection.starts_with("Words Progress") {
+            app.settings.progress_format = app.settings.progress_format.next();
+        } else if selection.starts_with("Text Columns") {
+            app.settings.columns = if app.settings.columns == 1 { 2 } else { 1 };
+        } else if selection.starts_with("Collapse Spaces") {
+            app.settings.collapse_spaces = !app.settings.collapse_spaces;
+            for err in &app.reload_words() {
+                gum_error(err);
+            }
+        } else if selection.starts_with("Number Format") {
+            app.settings.number_format = app.settings.number_format.next();
+        } else if selection.starts_with("Show Whitespace") {
+            app.settings.show_whitespace = !app.settings.show_whitespace;
+        } else if selection.starts_with("Ephemeral Mode") {
+            app.settings.ephemeral = !app.settings.ephemeral;
+        } else if selection.starts_with("Finish Animation") {
+            app.settings.completion_animation = !app.settings.completion_animation;
+        } else if selection.starts_with("Finish Sound") {
+            app.settings.completion_sound = !app.settings.completion_sound;
+        } else if selection.starts_with("Warm-up Gauge") {
+            app.settings.show_warmup = !app.settings.show_warmup;
+        } else if selection.starts_with("Word Separator") {
+            let val = gum_input("Separator: 'space', 'newline' or a single character", "space", &separator_name(app.settings.word_separator))?;
+            match val.as_str() {
+                "space" => app.settings.word_separator = ' ',
+                "newline" => app.settings.word_separator = '\n',
+                other => {
+                    let mut chars = other.chars();
+                    if let (Some(c), None) = (chars.next(), chars.next()) {
+                        app.settings.word_separator = c;
+                    }
+                }
+            }
+        } else if selection.starts_with("Word Lists") {
+            let files = find_word_lists();
+            let mut opts_str = vec!["Default"];
+            opts_str.extend(files.iter().map(|f| f.as_str()));
+            let picked = gum_choose_many("Word lists to mix (Space to mark, Enter to confirm)", &opts_str, opts_str.len(), None)?;
+            if !picked.is_empty() {
+                app.settings.word_lists = picked.into_iter().filter(|p| p != "Default").collect();
+                for err in &app.reload_words() {
+                    gum_error(err);
+                }
+                gum_style(&format!("{} words loaded", app.words_list.len()))?;
+                wait_for_enter();
+            }
+        } else if selection.starts_with("Import Monkeytype List") {
+            let path = gum_input("Monkeytype word list (JSON with a \"words\" list)", "wordlists/english.json", "")?;
+            if path.is_empty() {
+                continue;
+            }
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| parse_monkeytype(&text)) {
+                Ok(words) => {
+                    if !app.settings.word_lists.contains(&path) {
+                        app.settings.word_lists.push(path.clone());
+                    }
+                    for err in &app.reload_words() {
+                        gum_error(err);
                     }
+                    gum_style(&format!("{} words imported from {}\n{} words in use now", words.len(), path, app.words_list.len()))?;
                 }
-                if !current_line.is_empty() {
-                    spans.push(Line::from(current_line));
+                Err(e) => gum_error(&format!("Couldn't import {}: {}. Word lists are unchanged.", path, e)),
+            }
+            wait_for_enter();
+        } else if selection.starts_with("Per-Mode Overrides") {
+            mode_overrides_menu(&mut app.settings)?;
+        } else if selection.starts_with("Words Count") {
+            app.settings.count_clean_words = !app.settings.count_clean_words;
+        } else if selection.starts_with("Distractions") {
+            app.settings.distractions = !app.settings.distractions;
+        } else if selection.starts_with("Error Ticker") {
+            app.settings.show_error_ticker = !app.settings.show_error_ticker;
+        } else if selection.starts_with("Minimap") {
+            app.settings.show_minimap = !app.settings.show_minimap;
+        } else if selection.starts_with("Length Ramp") {
+            app.settings.auto_length_ramp = !app.settings.auto_length_ramp;
+        } else if selection.starts_with("Pinned Words") {
+            let val = gum_input("Words to include in every text (separated by spaces or commas, empty for none)", "word another", &app.settings.pinned_words.join(" "))?;
+            let mut seen = HashSet::new();
+            app.settings.pinned_words = val
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .map(|w| clean_entry(w, true))
+                .filter(|w| !w.is_empty() && seen.insert(w.clone()))
+                .collect();
+        } else if selection.starts_with("Preview Words Text") {
+            app.settings.preview_words = !app.settings.preview_words;
+        } else if selection.starts_with("Sequential List Order") {
+            app.settings.list_order = app.settings.list_order.next();
+            app.ordered_list = None;
+            app.sequential_cursor = 0;
+        } else if selection.starts_with("Non-adaptive Order") {
+            app.settings.sequential_order = !app.settings.sequential_order;
+        } else if selection.starts_with("Adaptive Words") {
+            if !app.settings.adaptive_selection
+                || gum_confirm("Use uniform random words? Results are only comparable with runs using the same setting.")
+            {
+                app.settings.adaptive_selection = !app.settings.adaptive_selection;
+            }
+        } else if selection.starts_with("Results Key Delay") {
+            if let Some(n) = gum_input_number("Milliseconds the results ignore keys for, so a last burst of typing can't skip them", "300", app.settings.results_input_delay_ms, RESULTS_DELAY_RANGE)? {
+                app.settings.results_input_delay_ms = n;
+            }
+        } else if selection.starts_with("Final Space Grace") {
+            if let Some(n) = gum_input_number("Milliseconds after the last word's final letter before a Words test ends without its space, 0 = space required", "400", app.settings.final_space_grace_ms, FINAL_SPACE_GRACE_RANGE)? {
+                app.settings.final_space_grace_ms = n;
+            }
+        } else if selection.starts_with("Autosave") {
+            if let Some(n) = gum_input_number("Seconds between stat saves during a test, 0 = only at the end", "60", app.settings.autosave_secs, AUTOSAVE_RANGE)? {
+                app.settings.autosave_secs = n;
+            }
+        } else if selection.starts_with("Practice Reminder") {
+            if let Some(n) = gum_input_number("Days without a test before the menu nudges you, 0 = off", "2", app.settings.reminder_days, REMINDER_DAYS_RANGE)? {
+                app.settings.reminder_days = n;
+            }
+        } else if selection.starts_with("Benchmarks") {
+            let mut value = app.settings.benchmark_wpm.map(|t| t.to_string()).join(" ");
+            loop {
+                let val = gum_input(&format!("WPM where {} start, ascending", BENCHMARK_NAMES.join("/")), "30 60 90", &value)?;
+                if val.is_empty() {
+                    break;
+                }
+                let parsed: Vec<f64> = val.split([' ', ',', '/']).filter(|p| !p.is_empty()).filter_map(|p| p.parse().ok()).collect();
+                if let Ok(thresholds) = <[f64; 3]>::try_from(parsed) {
+                    if thresholds.windows(2).all(|w| w[0] < w[1]) && thresholds[0] > 0.0 {
+                        app.settings.benchmark_wpm = thresholds;
+                        break;
+                    }
                 }
+                gum_error(&format!("'{}' isn't three ascending numbers like 30 60 90", val));
+                value = val;
+            }
+        } else if selection.starts_with("Goal") {
+            if let Some(n) = gum_input_number("Target WPM, 0 = no goal", "80", app.settings.goal_wpm, GOAL_RANGE)? {
+                app.settings.goal_wpm = n;
+            }
+        } else if selection.starts_with("Accuracy Floor") {
+            if let Some(n) = gum_input_number("Minimum running accuracy in %, 0 = off", "90", app.settings.accuracy_floor, ACCURACY_FLOOR_RANGE)? {
+                app.settings.accuracy_floor = n;
+            }
+        } else if selection.starts_with("Below Floor") {
+            app.settings.accuracy_floor_ends_test = !app.settings.accuracy_floor_ends_test;
+        } else if selection.starts_with("Theme") {
+            app.settings.theme = app.settings.theme.next();
+        } else if selection.starts_with("Alternate Screen") {
+            app.settings.use_alt_screen = !app.settings.use_alt_screen;
+        } else if selection.starts_with("Large Text") {
+            app.settings.large_text = !app.settings.large_text;
+        } else if selection.starts_with("Auto Space") {
+            app.settings.auto_space = !app.settings.auto_space;
+        } else if selection.starts_with("Enter as Space") {
+            app.settings.enter_as_space = !app.settings.enter_as_space;
+        } else if selection.starts_with("Backspace Penalty") {
+            if let Some(n) = gum_input_number("Characters subtracted from WPM per backspace", "0", app.settings.backspace_penalty, BACKSPACE_PENALTY_RANGE)? {
+                app.settings.backspace_penalty = n;
+            }
+        } else if selection.starts_with("Paste Handling") {
+            app.settings.paste_policy = app.settings.paste_policy.next();
+        } else if selection.starts_with("Default Time") {
+            if let Some(n) = gum_input_number("Set Time Limit in seconds", "60", app.settings.default_time_limit, TIME_LIMIT_RANGE)? {
+                app.settings.default_time_limit = n;
+            }
+        } else if selection.starts_with("Default Words") {
+            if let Some(n) = gum_input_number("Set Word Limit", "25", app.settings.default_words_limit, WORDS_LIMIT_RANGE)? {
+                app.settings.default_words_limit = n;
+            }
+        } else if selection.starts_with("Default Chars") {
+            if let Some(n) = gum_input_number("Set Character Limit", "200", app.settings.default_chars_limit, CHARS_LIMIT_RANGE)? {
+                app.settings.default_chars_limit = n;
             }
+        } else if selection.starts_with("Pressure Start") {
+            if let Some(n) = gum_input_number("Seconds allowed for the first word", "5", app.settings.pressure_start_budget, PRESSURE_START_RANGE)? {
+                app.settings.pressure_start_budget = n;
+            }
+        } else if selection.starts_with("Pressure Decay") {
+            if let Some(n) = gum_input_number("Budget multiplier per word survived", "0.95", app.settings.pressure_decay, PRESSURE_DECAY_RANGE)? {
+                app.settings.pressure_decay = n;
+            }
+        } else if selection.starts_with("Advanced") {
+            advanced_settings_menu(app)?;
+        } else if selection.starts_with("Reset History") && gum_confirm("Are you sure?") {
+            app.user_data = UserData::default();
+        }
+    }
+    save_or_warn(app);
+    Ok(())
+}
 
-            f.render_widget(
-                Paragraph::new(spans).block(Block::default().padding(ratatui::widgets::Padding::new(2,2,1,1)))
-                .style(Style::default().bg(Color::Rgb(20, 20, 20))), 
-                layout[1]
-            );
+// Tuning for the adaptive word selection
+fn advanced_settings_menu(app: &mut AppState) -> Result<()> {
+    loop {
+        let options = [
+            format!("Accuracy Weight: {:.2}", app.settings.accuracy_exponent),
+            format!("Frequency Weight: {:.2}", app.settings.frequency_exponent),
+            format!("Speed Weight: {:.2}", app.settings.speed_exponent),
+            "Restore Defaults".to_string(),
+            "Back".to_string()
+        ];
 
-            // Footer Area
-            f.render_widget(
-                Paragraph::new("ESC: Quit").alignment(Alignment::Center).style(Style::default().fg(Color::Gray).bg(Color::Black)),
-                layout[2]
-            );
+        let opts_str: Vec<&str> = options.iter().map(|s| s.as_str()).collect();
+        let selection = gum_choose("Advanced: word selection exponents (higher = stronger effect)", &opts_str)?;
+
+        if selection.starts_with("Accuracy Weight") {
+            if let Some(n) = gum_input_number("Exponent on inverse accuracy", "1.0", app.settings.accuracy_exponent, EXPONENT_RANGE)? {
+                app.settings.accuracy_exponent = n;
+            }
+        } else if selection.starts_with("Frequency Weight") {
+            if let Some(n) = gum_input_number("Exponent on English letter frequency", "1.0", app.settings.frequency_exponent, EXPONENT_RANGE)? {
+                app.settings.frequency_exponent = n;
+            }
+        } else if selection.starts_with("Speed Weight") {
+            if let Some(n) = gum_input_number("Exponent on inverse letter speed", "1.0", app.settings.speed_exponent, EXPONENT_RANGE)? {
+                app.settings.speed_exponent = n;
+            }
+        } else if selection.starts_with("Restore Defaults") {
+            let defaults = Settings::default();
+            app.settings.accuracy_exponent = defaults.accuracy_exponent;
+            app.settings.frequency_exponent = defaults.frequency_exponent;
+            app.settings.speed_exponent = defaults.speed_exponent;
+        } else {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn format_results(res: &TestResult, settings: &Settings) -> String {
+    let nf = settings.number_format;
+    let mut text = format!(
+        "WPM: {}\nRaw WPM: {}\nAccuracy: {}%\nWord Accuracy: {}%\nTime: {}s\nWords: {}\nBackspaces: {}\nLevel: {}",
+        nf.float(res.wpm, 2), nf.float(res.raw_wpm, 2), nf.float(res.accuracy, 2), nf.float(res.word_accuracy, 2),
+        nf.float(res.time_taken, 2), nf.int(res.words_typed), nf.int(res.backspaces),
+        benchmark_band(&settings.benchmark_wpm, res.wpm)
+    );
+    text.push_str(&format!("\nBest Streak: {}{}", nf.int(res.best_streak), if res.streak_record { " - new record!" } else { "" }));
+    if let Some(c) = res.char_breakdown {
+        text.push_str(&format!(
+            "\nCharacters: {} correct / {} incorrect / {} extra / {} missed",
+            nf.int(c.correct), nf.int(c.incorrect), nf.int(c.extra), nf.int(c.missed)
+        ));
+    }
+    if let Some(failed) = res.failed_words {
+        text.push_str(&format!("\nFailed Words: {} (no WPM credit)", nf.int(failed)));
+    }
+    if let Some(slips) = res.case_slips {
+        text.push_str(&format!("\nCase Slips: {} (wrong case only)", nf.int(slips)));
+    }
+    if let Some(survived) = res.words_survived {
+        text.push_str(&format!("\nWords Survived: {}", nf.int(survived)));
+    }
+    if res.distractions {
+        text.push_str("\nDistractions: On");
+    }
+    if let Some(floor) = res.below_floor {
+        text.push_str(&format!("\nEnded Early: accuracy fell below the {}% floor", nf.float(floor, 0)));
+    }
+    if let Some(reason) = &res.invalid_reason {
+        text.push_str(&format!("\n\nNot saved: {}", reason));
+    }
+    text
+}
 
-        })?; // End of draw closure
+// Headline stats for sharing: (label, value), WPM first
+fn card_stats(res: &TestResult, nf: NumberFormat) -> Vec<(&'static str, String)> {
+    let mode = res.mode.map(|m| m.label()).unwrap_or_else(|| "Test".to_string());
+    let mode = if res.daily.is_some() { format!("{} (Daily Challenge)", mode) } else { mode };
+    vec![
+        ("WPM", nf.float(res.wpm, 1)),
+        ("Accuracy", format!("{}%", nf.float(res.accuracy, 1))),
+        ("Mode", mode),
+        ("Date", res.timestamp.format("%Y-%m-%d %H:%M").to_string()),
+    ]
+}
 
-        // Input Handling
-        if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Esc => should_exit = true,
-                        KeyCode::Backspace => {
-                            if !input_text.is_empty() {
-                                input_text.pop();
-                            }
-                        }
-                        KeyCode::Char(c) => {
-                            if !is_started {
-                                is_started = true;
-                                real_start_time = Instant::now();
-                                last_keystroke = real_start_time;
-                            }
+// Plain text in a box, for chats and forums that show monospace
+fn format_card_text(res: &TestResult, nf: NumberFormat) -> String {
+    let mut lines = vec!["musical-typing".to_string(), String::new()];
+    lines.extend(card_stats(res, nf).into_iter().map(|(label, value)| format!("{:<9} {}", label, value)));
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut card = format!("╭{}╮\n", "─".repeat(width + 2));
+    for line in &lines {
+        card.push_str(&format!("│ {}{} │\n", line, " ".repeat(width - line.chars().count())));
+    }
+    card.push_str(&format!("╰{}╯\n", "─".repeat(width + 2)));
+    card
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Hand-written SVG, no rendering crate: a dark card with the WPM large and the rest below
+fn format_card_svg(res: &TestResult, nf: NumberFormat) -> String {
+    let stats = card_stats(res, nf);
+    let mut svg = String::from(concat!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"420\" height=\"220\" viewBox=\"0 0 420 220\">\n",
+        "  <rect x=\"2\" y=\"2\" width=\"416\" height=\"216\" rx=\"14\" fill=\"#1e1b2e\" stroke=\"#ff87d7\" stroke-width=\"3\"/>\n",
+        "  <text x=\"24\" y=\"40\" font-family=\"monospace\" font-size=\"16\" fill=\"#b4a7d6\">musical-typing</text>\n",
+    ));
+    let (_, wpm) = &stats[0];
+    svg.push_str(&format!(
+        "  <text x=\"24\" y=\"100\" font-family=\"monospace\" font-size=\"48\" font-weight=\"bold\" fill=\"#ffffff\">{} <tspan font-size=\"20\" fill=\"#ff87d7\">WPM</tspan></text>\n",
+        xml_escape(wpm)
+    ));
+    for (i, (label, value)) in stats.iter().skip(1).enumerate() {
+        svg.push_str(&format!(
+            "  <text x=\"24\" y=\"{}\" font-family=\"monospace\" font-size=\"16\" fill=\"#d0d0d0\">{}: {}</text>\n",
+            140 + i * 26, label, xml_escape(value)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Writes an SVG when the path ends in .svg, the boxed text otherwise
+fn export_card(res: &TestResult, nf: NumberFormat) -> Result<()> {
+    gum_style(&format_card_text(res, nf))?;
+    let path = gum_input("Save card to (.svg for an image, anything else for text)", "typing-card.svg", "typing-card.svg")?;
+    if path.is_empty() {
+        return Ok(());
+    }
+    let card = if path.to_lowercase().ends_with(".svg") { format_card_svg(res, nf) } else { format_card_text(res, nf) };
+    match fs::write(&path, card) {
+        Ok(()) => gum_style(&format!("Card written to {}", path))?,
+        Err(e) => gum_error(&format!("Could not write {}: {}", path, e)),
+    }
+    wait_for_enter();
+    Ok(())
+}
+
+// Keys still being mashed as a fast run ends would otherwise pick the first results option.
+// Raw mode, so they're read (and dropped) one by one instead of waiting in the line buffer.
+fn discard_input_for(delay: Duration) -> Result<()> {
+    if delay.is_zero() {
+        return Ok(());
+    }
+    let deadline = Instant::now() + delay;
+    crossterm::terminal::enable_raw_mode()?;
+    let drained = (|| -> io::Result<()> {
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            if event::poll(left)? {
+                event::read()?;
+            }
+        }
+        Ok(())
+    })();
+    crossterm::terminal::disable_raw_mode()?;
+    Ok(drained?)
+}
+
+// True when the user asked to drill the missed words straight away
+fn show_results(res: &TestResult, settings: &Settings) -> Result<bool> {
+    gum_style(&format_results(res, settings))?;
+    discard_input_for(Duration::from_millis(settings.results_input_delay_ms))?;
+    if res.typed_text.is_empty() {
+        wait_for_enter();
+        return Ok(false);
+    }
+    let mut options = vec!["Continue", "Review Mistakes"];
+    if !res.missed_words.is_empty() {
+        options.push("Drill Mistakes");
+    }
+    options.push("Share Card");
+    loop {
+        match gum_choose("Results", &options)?.as_str() {
+            "Review Mistakes" => {
+                review_mistakes(res, settings.theme)?;
+                gum_style(&format_results(res, settings))?;
+            }
+            "Share Card" => {
+                export_card(res, settings.number_format)?;
+                gum_style(&format_results(res, settings))?;
+            }
+            "Drill Mistakes" => return Ok(true),
+            _ => return Ok(false),
+        }
+    }
+}
+
+// A short Words test made only of the given words, each coming up a few times
+fn drill_mistakes(app: &mut AppState, words: &[String]) -> Result<Option<TestResult>> {
+    app.drill_words = Some(words.to_vec());
+    let count = (words.len() * DRILL_REPEATS).clamp(5, 50);
+    let res = run_test(app, TestMode::Words(count));
+    app.drill_words = None;
+    res
+}
+
+// --- Command Line ---
+
+const USAGE: &str = "Usage: typr-rs [--words N | --time SECONDS | --chars N | --forever] [--ephemeral] [--seed N] [--focus LETTERS] [--verbose]
+       typr-rs --print-words N [--seed N] [--focus LETTERS]
+       typr-rs --diagnostics
+
+--ephemeral keeps everything in memory, nothing is loaded from or saved to disk.
+--print-words prints N generated words and exits, without gum or the TUI.
+--seed makes word generation reproducible.
+--focus strongly favours words containing LETTERS, on top of the usual weighting, for this session.
+--diagnostics prints version, paths and data file checks for bug reports, and exits.
+--verbose appends test, text generation and save events to musical-typing.log (so does setting RUST_LOG).
+
+Without a mode flag the interactive gum menu is shown.
+Piping text into stdin uses it as the word list, e.g.
+    cat mywords.txt | typr-rs --words 30
+Piped input requires a mode flag, since gum needs stdin for its menus.";
+
+#[derive(Default)]
+struct CliArgs {
+    mode: Option<TestMode>, // Skip the menu and run this test directly
+    ephemeral: bool,
+    print_words: Option<usize>, // Headless: print generated text and exit
+    seed: Option<u64>,
+    focus: String, // Letters to favour for this session only
+    diagnostics: bool,
+    verbose: bool, // Log events to musical-typing.log
+}
+
+fn parse_args() -> Result<CliArgs> {
+    let mut cli = CliArgs::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--words" => cli.mode = Some(TestMode::Words(flag_value(&mut args, &arg)?)),
+            "--time" => cli.mode = Some(TestMode::Time(flag_value(&mut args, &arg)?)),
+            "--chars" => cli.mode = Some(TestMode::Chars(flag_value(&mut args, &arg)?)),
+            "--forever" => cli.mode = Some(TestMode::Forever),
+            "--ephemeral" => cli.ephemeral = true,
+            "--print-words" => cli.print_words = Some(flag_value(&mut args, &arg)?),
+            "--seed" => cli.seed = Some(flag_value(&mut args, &arg)?),
+            "--focus" => cli.focus = args.next().context("--focus expects letters, e.g. --focus qz")?,
+            "--diagnostics" => cli.diagnostics = true,
+            "--verbose" => cli.verbose = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => anyhow::bail!("Unknown argument '{}'\n\n{}", other, USAGE),
+        }
+    }
+    Ok(cli)
+}
 
-                            // Process character if text not done
-                            if input_text.len() < target_text.len() {
-                                let now = Instant::now();
-                                let delta = now.duration_since(last_keystroke).as_secs_f64();
-                                last_keystroke = now;
+fn flag_value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T> {
+    args.next()
+        .and_then(|v| v.parse().ok())
+        .with_context(|| format!("{} expects a number", flag))
+}
 
-                                let target_char = target_text.chars().nth(input_text.len()).unwrap();
-                                let is_correct = c == target_char;
-                                
-                                app.update_stats(target_char, is_correct, delta);
+// One line per data file: whether it parses and how much it holds, never the contents
+fn diagnose_file<T: DeserializeOwned>(path: &str, summary: impl Fn(&T) -> String) -> String {
+    match fs::read_to_string(path) {
+        Ok(s) => match serde_json::from_str::<T>(&s) {
+            Ok(data) => format!("{}: OK, {}", path, summary(&data)),
+            Err(e) => format!("{}: does not parse ({})", path, e),
+        },
+        Err(e) if e.kind() == ErrorKind::NotFound => format!("{}: missing (defaults are used)", path),
+        Err(e) => format!("{}: unreadable ({})", path, e),
+    }
+}
 
-                                if is_correct || !app.settings.forgive_errors {
-                                    input_text.push(c);
-                                } else if app.settings.forgive_errors && !is_correct {
-                                    // Block input (do nothing)
-                                }
-                            }
+// --diagnostics: read-only report that is safe to paste into an issue
+fn print_diagnostics() {
+    println!("typr-rs {}", env!("CARGO_PKG_VERSION"));
+    println!("OS: {} {}", std::env::consts::OS, std::env::consts::ARCH);
+    match std::env::current_dir() {
+        Ok(dir) => println!("Data directory: {}", dir.display()),
+        Err(e) => println!("Data directory: unknown ({})", e),
+    }
+    match SysCommand::new("gum").arg("--version").output() {
+        Ok(out) => println!("gum: {}", String::from_utf8_lossy(&out.stdout).trim()),
+        Err(_) => println!("gum: not found"),
+    }
+    match crossterm::terminal::size() {
+        Ok((w, h)) => println!("Terminal: {}x{}", w, h),
+        Err(e) => println!("Terminal: size unknown ({})", e),
+    }
+    println!("stdin is a terminal: {}", io::stdin().is_terminal());
 
-                            // Check Word Limit Completion
-                            if let TestMode::Words(limit) = mode {
-                                let words_typed = input_text.split_whitespace().count();
-                                if words_typed >= limit && input_text.ends_with(' ') {
-                                    completed = true;
-                                }
-                                if input_text.len() == target_text.len() {
-                                    completed = true;
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
+    println!("{}", diagnose_file("settings.json", |s: &Settings| {
+        let bindings = match s.keybindings.resolve() {
+            Ok(_) => "key bindings OK".to_string(),
+            Err(e) => format!("key bindings invalid: {}", e),
+        };
+        format!("{} word list(s) selected, {}", s.word_lists.len(), bindings)
+    }));
+    println!("{}", diagnose_file("userdata.json", |d: &UserData| {
+        format!("{} tests, {} letters tracked", d.test_history.len(), d.letter_shown.len())
+    }));
+    println!("{}", diagnose_file("drills.json", |d: &Vec<SavedDrill>| format!("{} saved drills", d.len())));
+    println!("{}", diagnose_file("locked.json", |l: &Option<LockedText>| match l {
+        Some(l) => format!("{} words locked, {} runs", l.words.len(), l.runs.len()),
+        None => "no text locked".to_string(),
+    }));
+    let mut errors = Vec::new();
+    let settings: Settings = read_json("settings.json", &mut errors).unwrap_or_default();
+    let words = load_words(&settings.word_lists, settings.collapse_spaces, &mut errors);
+    println!("Word list: {} words", words.len());
+    for err in errors.iter().filter(|e| e.starts_with("Couldn't read word list")) {
+        println!("  {}", err);
+    }
+}
+
+// Runs a single test straight from the command line, without any gum menus.
+fn run_direct(app: &mut AppState, mode: TestMode) -> Result<()> {
+    for err in &app.load_errors {
+        eprintln!("Warning: {}", err);
+    }
+    if let Some(res) = run_test(app, mode)? {
+        if let Err(e) = app.record_result(&res) {
+            eprintln!("Warning: save failed: {:#}", e);
         }
-    } // End of While Loop
+        println!("{}", format_results(&res, &app.settings));
+    }
+    if app.unsaved_stats {
+        if let Err(e) = app.save() {
+            eprintln!("Warning: save failed: {:#}", e);
+        }
+    }
+    Ok(())
+}
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+// A first SIGINT only sets the flag so stats can be flushed; a second one exits immediately.
+fn install_interrupt_handler(flag: &Arc<AtomicBool>) -> Result<()> {
+    use signal_hook::consts::SIGINT;
+    signal_hook::flag::register_conditional_shutdown(SIGINT, 130, Arc::clone(flag))?;
+    signal_hook::flag::register(SIGINT, Arc::clone(flag))?;
+    Ok(())
+}
 
-    if completed {
-        let elapsed = real_start_time.elapsed().as_secs_f64();
-        let chars = input_text.len();
-        let words = input_text.split_whitespace().count();
-        let raw_wpm = (chars as f64 / 5.0) / (elapsed / 60.0);
-        
-        let mut correct_chars = 0;
-        for (i, c) in input_text.chars().enumerate() {
-            if i < target_text.len() && target_text.chars().nth(i) == Some(c) {
-                correct_chars += 1;
-            }
+// Blocks until the terminal is at least `min` (columns, rows). False if Ctrl+C was pressed instead.
+fn wait_for_terminal_size(min: (u16, u16), interrupted: &AtomicBool) -> bool {
+    let mut warned = false;
+    while let Ok((w, h)) = crossterm::terminal::size() {
+        if w >= min.0 && h >= min.1 {
+            break;
         }
-        let accuracy = if chars > 0 { correct_chars as f64 / chars as f64 } else { 0.0 };
-        let net_wpm = raw_wpm * accuracy;
+        if interrupted.load(Ordering::Relaxed) {
+            return false;
+        }
+        if !warned {
+            eprintln!(
+                "Terminal is {}x{}, but at least {}x{} is needed. Resize to continue (Ctrl+C to quit).",
+                w, h, min.0, min.1
+            );
+            warned = true;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+    true
+}
 
-        Ok(Some(TestResult {
-            timestamp: Local::now(),
-            raw_wpm,
-            wpm: net_wpm,
-            accuracy: accuracy * 100.0,
-            time_taken: elapsed,
-            text_length: chars,
-            words_typed: words,
-        }))
-    } else {
-        Ok(None)
+// Menu-launched test: remembered so it can be repeated on the next launch
+fn start_test(app: &mut AppState, mode: TestMode) -> Result<Option<TestResult>> {
+    if app.settings.last_mode != Some(mode) {
+        app.settings.last_mode = Some(mode);
+        save_or_warn(app);
+    }
+    if let (TestMode::Words(count), true) = (mode, app.settings.preview_words) {
+        match preview_text(app, count)? {
+            Some(text) => app.prepared_text = Some(text),
+            None => return Ok(None),
+        }
     }
+    run_test(app, mode)
 }
 
-// --- Menus ---
+const PREVIEW_WIDTH: usize = 60;
 
-fn settings_menu(app: &mut AppState) -> Result<()> {
+// Words test text shown up front: Start keeps it, Reroll draws another, Back (None) cancels
+fn preview_text(app: &mut AppState, count: usize) -> Result<Option<String>> {
     loop {
-        // Clone simple Copy types to avoid borrow issues
-        let options = vec![
-            format!("Forgive Errors: {}", if app.settings.forgive_errors { "On" } else { "Off" }),
-            format!("Default Time: {}s", app.settings.default_time_limit),
-            format!("Default Words: {}", app.settings.default_words_limit),
-            format!("Live WPM: {}", if app.settings.show_wpm_live { "On" } else { "Off" }),
-            "Reset History".to_string(),
-            "Back".to_string()
-        ];
-        
-        let opts_str: Vec<&str> = options.iter().map(|s| s.as_str()).collect();
-        let selection = gum_choose("Settings", &opts_str)?;
-
-        if selection.starts_with("Back") {
-            break;
-        } else if selection.starts_with("Forgive") {
-            app.settings.forgive_errors = !app.settings.forgive_errors;
-        } else if selection.starts_with("Live WPM") {
-            app.settings.show_wpm_live = !app.settings.show_wpm_live;
-        } else if selection.starts_with("Default Time") {
-            let val = gum_input("Set Time Limit (seconds)", "60", &app.settings.default_time_limit.to_string())?;
-            if let Ok(n) = val.parse() { app.settings.default_time_limit = n; }
-        } else if selection.starts_with("Default Words") {
-            let val = gum_input("Set Word Limit", "25", &app.settings.default_words_limit.to_string())?;
-            if let Ok(n) = val.parse() { app.settings.default_words_limit = n; }
-        } else if selection.starts_with("Reset History") {
-            if gum_confirm("Are you sure?") {
-                app.user_data = UserData::default();
+        let text = app.generate_text(count);
+        let difficulty = app.difficulty_ratio(&text);
+        // gum style doesn't wrap, so break between words at PREVIEW_WIDTH
+        let mut lines = vec![String::new()];
+        for word in text.split(app.settings.word_separator) {
+            let line = lines.last_mut().unwrap();
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > PREVIEW_WIDTH {
+                lines.push(word.to_string());
+            } else {
+                if !line.is_empty() {
+                    line.push(app.settings.word_separator);
+                }
+                line.push_str(word);
             }
         }
+        let _ = SysCommand::new("clear").status();
+        gum_style(&format!("{}\n\nDifficulty: {} ({:.2}x)", lines.join("\n"), difficulty_label(difficulty), difficulty))?;
+        match gum_choose("Use this text?", &["Start", "Reroll", "Back"])?.as_str() {
+            "Start" => return Ok(Some(text)),
+            "Reroll" => continue,
+            _ => return Ok(None),
+        }
     }
-    app.save();
-    Ok(())
 }
 
+fn goal_progress(app: &AppState) -> Option<String> {
+    if app.settings.goal_wpm <= 0.0 {
+        return None;
+    }
+    let best = app.user_data.best_wpm().unwrap_or(0.0);
+    let percent = (best / app.settings.goal_wpm * 100.0).min(100.0);
+    let nf = app.settings.number_format;
+    Some(format!("Goal: {}/{} WPM ({}%)", nf.float(best, 0), nf.float(app.settings.goal_wpm, 0), nf.float(percent, 0)))
+}
 
-fn show_results(res: TestResult) -> Result<()> {
-    let text = format!(
-        "WPM: {:.2}\nRaw WPM: {:.2}\nAccuracy: {:.2}%\nTime: {:.2}s\nWords: {}",
-        res.wpm, res.raw_wpm, res.accuracy, res.time_taken, res.words_typed
-    );
-    gum_style(&text)?;
-    // Pause for user
-    let _ = SysCommand::new("gum").arg("format").arg("Press Enter...").status();
-    let _ = std::io::stdin().read_line(&mut String::new());
+// Once the best saved run beats the goal: celebrate and ask for the next one
+fn check_goal(app: &mut AppState) -> Result<()> {
+    let goal = app.settings.goal_wpm;
+    if goal <= 0.0 || app.user_data.best_wpm().unwrap_or(0.0) < goal {
+        return Ok(());
+    }
+    gum_style(&format!("Goal reached: {} WPM!", app.settings.number_format.float(goal, 0)))?;
+    // Keeping the old goal would just celebrate again next time
+    app.settings.goal_wpm = gum_input_number("Next goal in WPM, empty = no goal", "", (goal + 10.0).round(), GOAL_RANGE)?.unwrap_or(0.0);
+    save_or_warn(app);
     Ok(())
 }
 
+// "It's been 3 days!" once the last test is older than `reminder_days`
+fn practice_reminder(app: &AppState) -> Option<String> {
+    if app.settings.reminder_days == 0 {
+        return None;
+    }
+    let last = app.user_data.test_history.last()?;
+    let days = (Local::now() - last.timestamp).num_days();
+    (days >= app.settings.reminder_days as i64).then(|| format!("It's been {} days since your last test!", days))
+}
+
 fn main() -> Result<()> {
+    let cli = parse_args()?;
+    if cli.diagnostics {
+        print_diagnostics();
+        return Ok(());
+    }
+    if let Err(e) = init_logging(cli.verbose) {
+        eprintln!("Warning: logging disabled: {:#}", e);
+    }
+    let mut app = AppState::load(cli.ephemeral);
+
+    // Piped stdin replaces the word list; the TUI still reads keys from the terminal
+    let piped = !io::stdin().is_terminal();
+    if piped {
+        let mut input = String::new();
+        io::stdin().read_to_string(&mut input)?;
+        let words: Vec<String> = input.split_whitespace().map(|s| s.nfc().collect()).collect();
+        if !words.is_empty() {
+            app.words_list = words;
+        }
+    }
+
+    if let Some(seed) = cli.seed {
+        app.rng = StdRng::seed_from_u64(seed);
+    }
+    app.focus_letters = parse_letters(&cli.focus);
+
+    if let Some(count) = cli.print_words {
+        println!("{}", app.generate_text(count));
+        return Ok(());
+    }
+    install_interrupt_handler(&app.interrupted)?;
+    if let Some(mode) = cli.mode {
+        return run_direct(&mut app, mode);
+    }
+    if piped {
+        eprintln!("Error: piped input needs a mode flag (e.g. --words 30), gum menus can't read from a pipe.");
+        return Ok(());
+    }
+
     // Check for gum installation
     if SysCommand::new("gum").arg("--version").output().is_err() {
         eprintln!("Error: 'gum' is not installed (https://github.com/charmbracelet/gum).");
         return Ok(());
     }
 
-    let mut app = AppState::load();
+    if !wait_for_terminal_size(app.settings.min_terminal_size, &app.interrupted) {
+        return Ok(());
+    }
 
-    loop {
+    for err in &app.load_errors {
+        gum_error(err);
+    }
+    if !app.load_errors.is_empty() {
+        wait_for_enter();
+    }
+
+    // Brand new profile: suggest calibrating, once
+    if !app.settings.calibration_offered && app.user_data.letter_shown.is_empty() && !app.is_ephemeral() {
+        app.settings.calibration_offered = true;
+        save_or_warn(&app);
+        if gum_confirm("New here? Type a short calibration text so word selection starts from your real strengths and weaknesses.") {
+            if let Some(res) = calibrate(&mut app)? {
+                if let Err(e) = app.record_result(&res) {
+                    gum_error(&format!("Save failed: {:#}", e));
+                }
+                gum_style(&format_results(&res, &app.settings))?;
+                wait_for_enter();
+            } else if app.unsaved_stats && save_or_warn(&app) {
+                app.unsaved_stats = false;
+            }
+        }
+    }
+
+    let mut last_selection: Option<String> = None; // Main menu entry picked last time, this session only
+    while !app.interrupted.load(Ordering::Relaxed) {
         let _ = SysCommand::new("clear").status();
-        let selection = gum_choose(
-            "TYPR - Rust Edition", 
-            &["Start Words Test", "Start Time Test", "Forever Mode", "Settings", "Exit"]
-        )?;
+        let mut header = "TYPR - Rust Edition".to_string();
+        if app.is_ephemeral() {
+            header.push_str("\nEphemeral mode: nothing is saved");
+        }
+        if let (Some(wpm), Some(acc)) = (app.user_data.average_wpm(), app.user_data.average_accuracy()) {
+            let nf = app.settings.number_format;
+            header.push_str(&format!("\n{} tests | avg {} WPM | {}% accuracy", nf.int(app.user_data.total_tests()), nf.float(wpm, 0), nf.float(acc, 1)));
+        }
+        if !app.focus_letters.is_empty() {
+            header.push_str(&format!("\nFocus letters: {}", app.focus_letters.iter().collect::<String>()));
+        }
+        if let Some(progress) = goal_progress(&app) {
+            header.push('\n');
+            header.push_str(&progress);
+        }
+        if let Some(nudge) = practice_reminder(&app) {
+            header.push('\n');
+            header.push_str(&nudge);
+        }
+        let repeat_label = app.settings.last_mode.map(|m| format!("Repeat Last Test ({})", m.label()));
+        let mut options: Vec<&str> = Vec::new();
+        if let Some(label) = &repeat_label {
+            options.push(label);
+        }
+        options.extend(["Daily Challenge", "Practice Weakest", "Common Words", "Start Words Test", "Start Time Test", "Start Chars Test", "Forever Mode", "Pressure Mode", "Exam Mode", "Custom Letters", "Focus Letters", "Saved Drills", "Locked Text", "Review Worst Run", "History", "Settings", "Exit"]);
+        // Back on the entry picked last time, so drilling the same thing again is one Enter;
+        // the Repeat Last label changes with the mode, so it's matched by prefix
+        let previous = last_selection.as_deref().and_then(|last| {
+            let repeat = last.starts_with("Repeat Last Test");
+            options.iter().copied().find(|o| *o == last || (repeat && o.starts_with("Repeat Last Test")))
+        });
+        let selection = gum_choose_at(&header, &options, previous)?;
+        last_selection = Some(selection.clone());
 
         let result = match selection.as_str() {
+            s if s.starts_with("Repeat Last Test") => match app.settings.last_mode {
+                Some(mode) => start_test(&mut app, mode)?,
+                None => None,
+            },
             "Start Words Test" => {
                 let limit = app.settings.default_words_limit;
-                run_test(&mut app, TestMode::Words(limit))?
+                start_test(&mut app, TestMode::Words(limit))?
             },
             "Start Time Test" => {
                 let limit = app.settings.default_time_limit;
-                run_test(&mut app, TestMode::Time(limit))?
+                start_test(&mut app, TestMode::Time(limit))?
+            },
+            "Start Chars Test" => {
+                let limit = app.settings.default_chars_limit;
+                start_test(&mut app, TestMode::Chars(limit))?
             },
             "Forever Mode" => {
-                run_test(&mut app, TestMode::Forever)?
+                start_test(&mut app, TestMode::Forever)?
+            },
+            "Pressure Mode" => {
+                start_test(&mut app, TestMode::Pressure)?
+            },
+            "Exam Mode" => {
+                // No live feedback at all for one run; the user's own settings come back afterwards
+                let saved = app.settings.clone();
+                app.settings.blind_mode = true;
+                app.settings.show_wpm_live = false;
+                app.settings.show_warmup = false;
+                // The floor's running accuracy and banner are live feedback too
+                app.settings.accuracy_floor = 0.0;
+                // Blocked keys and the red flash both point out every wrong key
+                app.settings.enforce_correction = false;
+                app.exam = true;
+                // Per-mode overrides could turn the feedback back on
+                app.settings.mode_overrides.clear();
+                let limit = app.settings.default_words_limit;
+                let res = run_test(&mut app, TestMode::Words(limit));
+                app.settings = saved;
+                app.exam = false;
+                res?
+            },
+            "Practice Weakest" => {
+                let weakest: Vec<char> = app.user_data.weakest_letters(PRACTICE_LETTERS).iter().map(|s| s.letter).collect();
+                if weakest.is_empty() {
+                    gum_error(&format!("Not enough data yet: letters need {} samples before they can be drilled.", MIN_LETTER_SAMPLES));
+                    wait_for_enter();
+                    None
+                } else {
+                    focus_drill(&mut app, weakest, PRACTICE_WORDS)?
+                }
+            },
+            "Common Words" => {
+                app.in_order = Some(0);
+                let limit = app.settings.default_words_limit;
+                let res = run_test(&mut app, TestMode::Words(limit));
+                app.in_order = None;
+                res?
+            },
+            "Custom Letters" => {
+                let letters = parse_letters(&gum_input("Letters to practice", "asdfjkl", "")?);
+                if letters.is_empty() {
+                    None
+                } else {
+                    app.letter_set = Some(letters);
+                    let limit = app.settings.default_words_limit;
+                    let res = run_test(&mut app, TestMode::Words(limit));
+                    app.letter_set = None;
+                    res?
+                }
+            },
+            "Focus Letters" => {
+                let current: String = app.focus_letters.iter().collect();
+                app.focus_letters = parse_letters(&gum_input("Letters to favour this session (empty = none)", "qz", &current)?);
+                None
+            },
+            "Saved Drills" => drills_menu(&mut app)?,
+            "Locked Text" => locked_text_menu(&mut app)?,
+            "Daily Challenge" => daily_challenge(&mut app)?,
+            "Review Worst Run" => review_worst(&mut app)?,
+            "History" => {
+                history_menu(&app)?;
+                None
             },
             "Settings" => {
                 settings_menu(&mut app)?;
@@ -608,14 +4423,596 @@ fn main() -> Result<()> {
             _ => None,
         };
 
-        if let Some(res) = result {
-            if app.settings.auto_save_results && res.accuracy >= app.settings.min_accuracy_to_save * 100.0 {
-                 app.user_data.test_history.push(res.clone());
-                    app.save();
+        // Drill Mistakes runs straight from the results, its result goes round again
+        let mut result = result;
+        while let Some(res) = result {
+            if let Err(e) = app.record_result(&res) {
+                gum_error(&format!("Save failed: {:#}\nThis result is kept for the session only.", e));
+            }
+            let drill = show_results(&res, &app.settings)?;
+            check_goal(&mut app)?;
+            result = if drill { drill_mistakes(&mut app, &res.missed_words)? } else { None };
         }
-        show_results(res)?;
+        if app.unsaved_stats {
+            // Abandoned test: no result, but its letter stats still count
+            if save_or_warn(&app) {
+                app.unsaved_stats = false;
+            }
         }
     } // End of Main Loop
+
+    if app.unsaved_stats {
+        save_or_warn(&app);
+    }
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // No files read or written, and a fixed seed so generated text is repeatable
+    fn test_app() -> AppState {
+        let mut app = AppState::load(true);
+        app.rng = StdRng::seed_from_u64(7);
+        app
+    }
+
+    #[test]
+    fn untimed_keystroke_counts_without_timing() {
+        let mut app = test_app();
+        app.update_stats('a', 'a', None, false);
+        assert_eq!(app.user_data.letter_shown[&'a'], 1);
+        assert_eq!(app.user_data.letter_correct[&'a'], 1);
+        assert!(!app.user_data.letter_time_total.contains_key(&'a'));
+        assert!(!app.user_data.letter_time_count.contains_key(&'a'));
+
+        app.update_stats('a', 'a', Some(0.2), false);
+        assert_eq!(app.user_data.letter_time_count[&'a'], 1);
+        assert!((app.user_data.letter_time_total[&'a'] - 0.2).abs() < 1e-9);
+    }
+
+    fn result(wpm: f64, accuracy: f64) -> TestResult {
+        TestResult {
+            timestamp: Local::now(),
+            raw_wpm: wpm,
+            wpm,
+            accuracy,
+            time_taken: 30.0,
+            text_length: 120,
+            words_typed: 25,
+            word_accuracy: accuracy,
+            backspaces: 0,
+            mode: Some(TestMode::Words(25)),
+            words_survived: None,
+            best_streak: 40,
+            streak_record: false,
+            failed_words: None,
+            case_slips: None,
+            distractions: false,
+            daily: None,
+            char_breakdown: None,
+            below_floor: None,
+            invalid_reason: None,
+            typed_text: String::new(),
+            target_text: String::new(),
+            missed_words: Vec::new(),
+        }
+    }
+
+    // Three saved tests, and letters with (shown, accuracy, wpm)
+    fn fixture_data() -> UserData {
+        let mut data = UserData { test_history: vec![result(40.0, 90.0), result(50.0, 95.0), result(60.0, 100.0)], ..Default::default() };
+        for (letter, shown, accuracy, wpm) in [('a', 50, 0.98, 60.0), ('q', 20, 0.70, 30.0), ('z', 20, 0.70, 20.0), ('x', 3, 0.10, 10.0), (' ', 80, 0.50, 90.0)] {
+            data.letter_shown.insert(letter, shown);
+            data.letter_accuracy.insert(letter, accuracy);
+            data.letter_wpm.insert(letter, wpm);
+        }
+        data
+    }
+
+    #[test]
+    fn aggregates_over_history() {
+        let data = fixture_data();
+        assert_eq!(data.total_tests(), 3);
+        assert_eq!(data.average_wpm(), Some(50.0));
+        assert_eq!(data.average_accuracy(), Some(95.0));
+        assert_eq!(data.best_wpm(), Some(60.0));
+    }
+
+    #[test]
+    fn aggregates_of_empty_history_are_none() {
+        let data = UserData::default();
+        assert_eq!(data.total_tests(), 0);
+        assert_eq!(data.average_wpm(), None);
+        assert_eq!(data.average_accuracy(), None);
+        assert!(data.best_result().is_none());
+    }
+
+    #[test]
+    fn weakest_letters_skip_thin_data_and_whitespace() {
+        let data = fixture_data();
+        // 'x' has too few samples and ' ' is whitespace; 'z' and 'q' tie on accuracy, slower first
+        let weakest: Vec<char> = data.weakest_letters(5).iter().map(|s| s.letter).collect();
+        assert_eq!(weakest, vec!['z', 'q', 'a']);
+        assert_eq!(data.weakest_letters(1).len(), 1);
+        let z = &data.weakest_letters(1)[0];
+        assert_eq!((z.shown, z.accuracy, z.wpm), (20, 0.70, 20.0));
+    }
+
+    #[test]
+    fn comma_separated_words_are_counted_by_the_separator() {
+        assert_eq!(count_words("one,two,three", ','), 3);
+        assert_eq!(count_words("one,two,", ','), 2);
+        // Spaces are part of a word when the separator is a comma
+        assert_eq!(count_words("one two,three", ','), 2);
+        assert_eq!(word_index_at("one,two,three", 4, ','), 1);
+        assert_eq!(word_index_at("one,two,three", 3, ','), 0);
+    }
+
+    #[test]
+    fn clean_words_with_a_comma_separator() {
+        let target = "one,two,,three,four";
+        let failed = HashSet::from([1]);
+        // Separator after "one" not typed yet
+        assert_eq!(clean_words_done(target, 3, ',', &failed), 0);
+        assert_eq!(clean_words_done(target, 4, ',', &failed), 1);
+        // "two" had a miss and the empty word between the commas doesn't count
+        assert_eq!(clean_words_done(target, 9, ',', &failed), 1);
+        assert_eq!(clean_words_done(target, 15, ',', &failed), 2);
+    }
+
+    #[test]
+    fn generated_words_use_the_separator() {
+        let mut app = test_app();
+        app.settings.word_separator = ',';
+        let text = app.generate_text(10);
+        assert_eq!(count_words(&text, ','), 10);
+        assert!(!text.contains(' '));
+    }
+
+    #[test]
+    fn wrapping_counts_wide_characters_as_two_cells() {
+        assert_eq!(wrap_rows("abcdef", 4), vec![0, 4]);
+        // 日 fills the row exactly, 本 moves down whole
+        assert_eq!(wrap_rows("ab日本cd", 4), vec![0, 3]);
+        assert_eq!(wrap_rows("a日b", 2), vec![0, 1, 2]);
+        // Too narrow for even one wide character: each still gets its own row
+        assert_eq!(wrap_rows("日本", 1), vec![0, 1]);
+        // Rows start at char indices, not bytes
+        assert_eq!(wrap_rows("日本語テキスト", 6), vec![0, 3, 6]);
+    }
+
+    #[test]
+    fn one_early_miss_does_not_dominate_selection() {
+        let mut app = app_with_weak_q();
+        // Accuracy only, so letter frequency can't hide the effect
+        app.settings.frequency_exponent = 0.0;
+        app.user_data.letter_correct.insert('a', 95);
+        let real_weakness = app.letter_weights()[&'q'];
+
+        app.user_data.letter_shown.insert('q', 1);
+        app.user_data.letter_correct.insert('q', 0);
+        assert!(app.letter_weights()[&'q'] < real_weakness);
+        assert!(share_of(&app.get_weighted_words(2000), "qqqq") < 0.6);
+    }
+
+    #[test]
+    fn finishing_exactly_at_the_target_length_completes() {
+        let none = HashSet::new();
+        let target = "one two three";
+        assert_eq!(test_progress(TestMode::Time(60), target, target, ' ', &none, false, 0), Completion::Done);
+        assert_eq!(test_progress(TestMode::Time(60), target, "one two", ' ', &none, false, 0), Completion::Running);
+        assert_eq!(test_progress(TestMode::Chars(7), target, "one two", ' ', &none, false, 0), Completion::Done);
+        // Fewer words generated than asked for still ends at the end of the text
+        assert_eq!(test_progress(TestMode::Words(5), target, target, ' ', &none, false, 0), Completion::Done);
+    }
+
+    #[test]
+    fn words_limit_needs_the_whole_separator_run() {
+        let none = HashSet::new();
+        let target = "one  two three";
+        assert_eq!(test_progress(TestMode::Words(1), target, "one", ' ', &none, false, 0), Completion::LastWordTyped);
+        assert_eq!(test_progress(TestMode::Words(1), target, "one ", ' ', &none, false, 0), Completion::Running);
+        assert_eq!(test_progress(TestMode::Words(1), target, "one  ", ' ', &none, false, 0), Completion::Done);
+    }
+
+    #[test]
+    fn last_word_waits_for_its_separator() {
+        let none = HashSet::new();
+        let target = "one two three";
+        assert_eq!(test_progress(TestMode::Words(3), target, "one two thre", ' ', &none, false, 0), Completion::Running);
+        // Without a grace the test stays open on this until the separator comes
+        assert_eq!(test_progress(TestMode::Words(3), target, target, ' ', &none, false, 0), Completion::LastWordTyped);
+        let typed = format!("{target} ");
+        assert_eq!(test_progress(TestMode::Words(3), &typed, &typed, ' ', &none, false, 0), Completion::Done);
+        assert_eq!(test_progress(TestMode::Words(2), target, "one two", ' ', &none, false, 0), Completion::LastWordTyped);
+        assert_eq!(test_progress(TestMode::Words(2), target, "one two ", ' ', &none, false, 0), Completion::Done);
+    }
+
+    #[test]
+    fn repeated_words_dont_use_up_the_words_limit() {
+        let none = HashSet::new();
+        // "one" was missed and queued again, so three words typed are only two of the three asked for
+        let target = "one one two three";
+        assert_eq!(test_progress(TestMode::Words(3), target, "onx one two ", ' ', &none, false, 1), Completion::Running);
+        assert_eq!(test_progress(TestMode::Words(3), target, "onx one two three", ' ', &none, false, 1), Completion::LastWordTyped);
+        let typed = format!("{target} ");
+        assert_eq!(test_progress(TestMode::Words(3), &typed, "onx one two three ", ' ', &none, false, 1), Completion::Done);
+    }
+
+    #[test]
+    fn failed_words_dont_count_when_counting_clean_words() {
+        let failed: HashSet<usize> = [0].into_iter().collect();
+        let target = "one two three four";
+        assert_eq!(test_progress(TestMode::Words(2), target, "onx two ", ' ', &failed, true, 0), Completion::Running);
+        assert_eq!(test_progress(TestMode::Words(2), target, "onx two three ", ' ', &failed, true, 0), Completion::Done);
+    }
+
+    #[test]
+    fn double_spaces_are_kept_unless_collapsed() {
+        assert_eq!(clean_entry("  a  b ", true), "a b");
+        assert_eq!(clean_entry("  a  b ", false), "a  b");
+        let none = HashSet::new();
+        assert_eq!(test_progress(TestMode::Time(60), "a  b", "a ", ' ', &none, false, 0), Completion::Running);
+        assert_eq!(test_progress(TestMode::Time(60), "a  b", "a  b", ' ', &none, false, 0), Completion::Done);
+    }
+
+    #[test]
+    fn final_space_grace_ends_the_last_word() {
+        let now = Instant::now();
+        let deadline = grace_deadline(&Completion::LastWordTyped, 500, None, now);
+        assert_eq!(deadline, Some(now + Duration::from_millis(500)));
+        let later = now + Duration::from_millis(200);
+        assert_eq!(grace_deadline(&Completion::LastWordTyped, 500, deadline, later), deadline);
+        assert_eq!(grace_deadline(&Completion::Running, 500, deadline, later), None);
+        // Without a grace only the separator ends the test
+        assert_eq!(grace_deadline(&Completion::LastWordTyped, 0, None, now), None);
+    }
+
+    #[test]
+    fn failed_terminal_setup_is_rolled_back() {
+        use std::cell::Cell;
+        let restored = Cell::new(false);
+        let res: Result<()> = guarded_setup(|| Ok(()), || anyhow::bail!("no alternate screen"), || restored.set(true));
+        assert!(res.is_err());
+        assert!(restored.get());
+
+        let restored = Cell::new(false);
+        assert_eq!(guarded_setup(|| Ok(()), || Ok(5), || restored.set(true)).unwrap(), 5);
+        assert!(!restored.get());
+
+        // Raw mode never came on: nothing to set up or undo
+        let ran = Cell::new(false);
+        let res: Result<()> = guarded_setup(|| Err(io::Error::other("not a tty")), || { ran.set(true); Ok(()) }, || ran.set(true));
+        assert!(res.is_err());
+        assert!(!ran.get());
+    }
+
+    // Through JSON and back, as saving and loading do
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+        serde_json::from_str(&serde_json::to_string_pretty(value).unwrap()).unwrap()
+    }
+
+    fn populated_result() -> TestResult {
+        TestResult {
+            daily: NaiveDate::from_ymd_opt(2026, 10, 15),
+            char_breakdown: Some(CharBreakdown { correct: 110, incorrect: 4, extra: 2, missed: 1 }),
+            words_survived: Some(12),
+            failed_words: Some(3),
+            case_slips: Some(1),
+            distractions: true,
+            below_floor: Some(90.0),
+            backspaces: 6,
+            streak_record: true,
+            invalid_reason: Some("paste detected".to_string()),
+            typed_text: "the quick".to_string(),
+            target_text: "the quick".to_string(),
+            missed_words: vec!["quick".to_string()],
+            ..result(72.5, 96.25)
+        }
+    }
+
+    #[test]
+    fn test_result_round_trips() {
+        let res = populated_result();
+        let back = round_trip(&res);
+        assert_eq!(back.timestamp, res.timestamp);
+        assert_eq!(back.mode, Some(TestMode::Words(25)));
+        assert_eq!(back.daily, NaiveDate::from_ymd_opt(2026, 10, 15));
+        let c = back.char_breakdown.unwrap();
+        assert_eq!((c.correct, c.incorrect, c.extra, c.missed), (110, 4, 2, 1));
+        assert_eq!((back.wpm, back.raw_wpm, back.accuracy, back.word_accuracy), (72.5, 72.5, 96.25, 96.25));
+        assert_eq!((back.words_survived, back.failed_words, back.case_slips), (Some(12), Some(3), Some(1)));
+        assert_eq!((back.backspaces, back.best_streak, back.distractions), (6, 40, true));
+        assert_eq!(back.below_floor, Some(90.0));
+        // Session-only fields are never written
+        assert!(!back.streak_record);
+        assert_eq!(back.invalid_reason, None);
+        assert!(back.typed_text.is_empty() && back.target_text.is_empty() && back.missed_words.is_empty());
+    }
+
+    #[test]
+    fn user_data_round_trips_char_keys() {
+        let mut data = fixture_data();
+        data.test_history.push(populated_result());
+        for (i, ch) in ['é', ' ', '\n', '\t', '日', '"', '\\'].into_iter().enumerate() {
+            data.letter_shown.insert(ch, 10 + i as u32);
+            data.letter_correct.insert(ch, i as u32);
+            data.letter_time_total.insert(ch, 1.5 * i as f64);
+            data.letter_time_count.insert(ch, i as u32);
+        }
+        data.letter_confusions.insert(' ', HashMap::from([('\t', 2), ('\n', 1)]));
+        data.letter_confusions.insert('ü', HashMap::from([('u', 3), ('日', 4)]));
+        data.best_streak = 321;
+        data.word_lengths.insert(4, WordLengthStat { words: 9, clean: 7, chars: 45, secs: 8.5 });
+
+        let back = round_trip(&data);
+        assert_eq!(back.letter_shown, data.letter_shown);
+        assert_eq!(back.letter_correct, data.letter_correct);
+        assert_eq!(back.letter_accuracy, data.letter_accuracy);
+        assert_eq!(back.letter_time_total, data.letter_time_total);
+        assert_eq!(back.letter_time_count, data.letter_time_count);
+        assert_eq!(back.letter_wpm, data.letter_wpm);
+        assert_eq!(back.letter_confusions, data.letter_confusions);
+        assert_eq!(back.best_streak, 321);
+        let stat = &back.word_lengths[&4];
+        assert_eq!((stat.words, stat.clean, stat.chars, stat.secs), (9, 7, 45, 8.5));
+        assert_eq!(back.test_history.len(), 4);
+        assert_eq!(back.test_history[3].timestamp, data.test_history[3].timestamp);
+        assert_eq!(back.test_history[3].daily, data.test_history[3].daily);
+    }
+
+    #[test]
+    fn user_data_from_before_newer_fields_loads() {
+        // No confusions, streak or word lengths: written before those existed
+        let json = r#"{"letter_shown": {"a": 3}, "letter_correct": {"a": 2}, "letter_accuracy": {}, "letter_time_total": {},
+            "letter_time_count": {}, "letter_wpm": {}, "test_history": []}"#;
+        let data: UserData = serde_json::from_str(json).unwrap();
+        assert_eq!(data.letter_shown[&'a'], 3);
+        assert!(data.letter_confusions.is_empty() && data.word_lengths.is_empty());
+        assert_eq!(data.best_streak, 0);
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let mut settings = Settings {
+            word_separator: '\n',
+            pinned_words: vec!["naïve".to_string(), "日本".to_string()],
+            last_mode: Some(TestMode::Time(30)),
+            list_order: ListOrder::Frequency,
+            number_format: NumberFormat::Space,
+            benchmark_wpm: [25.0, 50.0, 75.0],
+            accuracy_exponent: 2.5,
+            ..Settings::default()
+        };
+        settings.keybindings.quit = "ctrl+q".to_string();
+        settings.mode_overrides.insert("Time".to_string(), ModeOverrides { forgive_errors: Some(true), blind_mode: Some(false), ..Default::default() });
+
+        let back = round_trip(&settings);
+        assert_eq!(back.word_separator, '\n');
+        assert_eq!(back.pinned_words, settings.pinned_words);
+        assert_eq!(back.last_mode, Some(TestMode::Time(30)));
+        assert_eq!(back.list_order, ListOrder::Frequency);
+        assert_eq!(back.number_format, NumberFormat::Space);
+        assert_eq!(back.benchmark_wpm, [25.0, 50.0, 75.0]);
+        assert_eq!(back.accuracy_exponent, 2.5);
+        assert_eq!(back.keybindings, settings.keybindings);
+        let overrides = &back.mode_overrides["Time"];
+        assert_eq!((overrides.forgive_errors, overrides.blind_mode, overrides.fail_words), (Some(true), Some(false), None));
+        // Nothing lost or renamed anywhere else either
+        assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(&settings).unwrap());
+    }
+
+    #[test]
+    fn empty_pool_falls_back_to_the_built_in_list() {
+        let mut app = test_app();
+        app.words_list = Vec::new();
+        assert_eq!(count_words(&app.get_weighted_words(15), ' '), 15);
+        app.words_list = vec![String::new(), String::new()];
+        assert_eq!(count_words(&app.get_weighted_words(15), ' '), 15);
+        app.settings.adaptive_selection = false;
+        assert_eq!(count_words(&app.get_weighted_words(15), ' '), 15);
+    }
+
+    #[test]
+    fn zero_and_broken_weights_still_pick_words() {
+        let mut app = test_app();
+        app.words_list = vec!["ab".to_string(), "cd".to_string()];
+        // Every letter fast and a huge speed exponent: every weight underflows to zero
+        for ch in 'a'..='z' {
+            app.user_data.letter_wpm.insert(ch, 1000.0);
+        }
+        app.settings.speed_exponent = 1000.0;
+        assert!(app.letter_weights().values().filter(|w| **w != 1.0).all(|w| *w == 0.0));
+        assert_eq!(count_words(&app.get_weighted_words(20), ' '), 20);
+
+        app.settings.speed_exponent = f64::NAN;
+        assert_eq!(count_words(&app.get_weighted_words(20), ' '), 20);
+    }
+
+    #[test]
+    fn decomposed_input_matches_a_composed_target() {
+        let composed = '\u{e9}'; // é
+        let acute = '\u{301}';
+        // A bare "e" waits for its mark, which then composes to the target itself
+        assert!(awaits_mark('e', Some(composed)));
+        assert_eq!(unicode_normalization::char::compose('e', acute), Some(composed));
+        // Already composed, or a target with no mark to wait for
+        assert!(!awaits_mark(composed, Some(composed)));
+        assert!(!awaits_mark('e', Some('e')));
+        assert!(!awaits_mark('a', Some(composed)));
+        assert!(!awaits_mark('e', None));
+        // Two marks: "ẹ" still waits for the circumflex of "ệ"
+        assert!(awaits_mark('\u{1eb9}', Some('\u{1ec7}')));
+        // No precomposed form: the base is judged alone
+        assert_eq!(unicode_normalization::char::compose('q', acute), None);
+        // Word lists are stored composed, so both spellings become the same target
+        assert_eq!(clean_entry("cafe\u{301}", true), clean_entry("caf\u{e9}", true));
+        assert_eq!(clean_entry("cafe\u{301}", true).chars().count(), 4);
+    }
+
+    #[test]
+    fn backspacing_to_empty_restarts_the_clock() {
+        let mut clock = TestClock::default();
+        assert!(clock.can_regenerate());
+        let first = Instant::now();
+        assert!(clock.key(first));
+        assert!(!clock.key(first + Duration::from_secs(1)));
+        assert_eq!(clock.start, Some(first));
+
+        let mut input = "日é".to_string();
+        clock.delete(&mut input);
+        assert_eq!(input, "日");
+        assert!(clock.running());
+        clock.delete(&mut input);
+        assert!(!clock.running() && clock.elapsed() == Duration::ZERO);
+
+        // The next key starts it again, but the text has been typed on
+        let later = first + Duration::from_secs(5);
+        assert!(clock.key(later));
+        assert_eq!(clock.start, Some(later));
+        assert!(!clock.can_regenerate());
+    }
+
+    #[test]
+    fn timing_samples_are_halved_keeping_the_average() {
+        let mut app = test_app();
+        app.user_data.letter_time_total.insert('a', 0.3 * MAX_TIME_SAMPLES as f64);
+        app.user_data.letter_time_count.insert('a', MAX_TIME_SAMPLES);
+        app.update_stats('a', 'a', Some(0.3), false);
+        let count = app.user_data.letter_time_count[&'a'];
+        assert_eq!(count, MAX_TIME_SAMPLES / 2);
+        assert!((app.user_data.letter_time_total[&'a'] / count as f64 - 0.3).abs() < 1e-9);
+
+        // Never above the cap however long it runs
+        for _ in 0..3 * MAX_TIME_SAMPLES {
+            app.update_stats('a', 'a', Some(0.3), false);
+            assert!(app.user_data.letter_time_count[&'a'] <= MAX_TIME_SAMPLES);
+        }
+        assert!((app.user_data.letter_wpm[&'a'] - 12.0 / 0.3).abs() < 1e-6);
+    }
+
+    // A fresh directory per test, so parallel tests never share files
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("musical-typing-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // read_or_protect keeps the path for the session, hence 'static
+    fn static_path(path: std::path::PathBuf) -> &'static str {
+        Box::leak(path.display().to_string().into_boxed_str())
+    }
+
+    #[test]
+    fn missing_data_file_gives_defaults_without_protection() {
+        let dir = temp_dir("missing");
+        let (mut errors, mut protected) = (Vec::new(), Vec::new());
+        let data: UserData = read_or_protect(static_path(dir.join("userdata.json")), &mut errors, &mut protected);
+        assert!(data.test_history.is_empty());
+        assert!(errors.is_empty());
+        assert!(protected.is_empty());
+    }
+
+    #[test]
+    fn corrupt_data_file_is_protected() {
+        let dir = temp_dir("corrupt");
+        let not_utf8 = static_path(dir.join("userdata.json"));
+        fs::write(not_utf8, b"{\"letter_shown\": {\"\xff\": 1}}").unwrap();
+        let not_json = static_path(dir.join("settings.json"));
+        fs::write(not_json, "{ not json").unwrap();
+
+        let (mut errors, mut protected) = (Vec::new(), Vec::new());
+        let data: UserData = read_or_protect(not_utf8, &mut errors, &mut protected);
+        assert!(data.letter_shown.is_empty());
+        let _: Settings = read_or_protect(not_json, &mut errors, &mut protected);
+        assert_eq!(protected, vec![not_utf8, not_json]);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("Could not read"));
+        assert!(errors[1].contains("Could not parse"));
+        // Still there for the user to rescue
+        assert_eq!(fs::read(not_utf8).unwrap(), b"{\"letter_shown\": {\"\xff\": 1}}");
+    }
+
+    #[test]
+    fn unreadable_word_list_is_reported() {
+        let dir = temp_dir("wordlist");
+        let list = dir.join("bad.txt");
+        fs::write(&list, b"good\n\xffbad\n").unwrap();
+        let mut errors = Vec::new();
+        let words = load_words(&[list.display().to_string()], true, &mut errors);
+        assert_eq!(errors.len(), 1);
+        // Nothing usable from the list: falls back rather than coming up empty
+        assert!(!words.is_empty());
+    }
+
+    #[test]
+    fn retrying_a_blocked_miss_counts_the_position_once() {
+        let mut app = test_app();
+        app.update_stats('t', 'r', Some(0.2), false);
+        // Blocked: two more wrong tries, then the right key, all at the same position
+        app.update_stats('t', 'y', Some(0.2), true);
+        app.update_stats('t', 'r', Some(0.2), true);
+        app.update_stats('t', 't', Some(0.2), true);
+        assert_eq!(app.user_data.letter_shown[&'t'], 1);
+        assert_eq!(app.user_data.letter_correct.get(&'t'), None);
+        assert_eq!(app.user_data.letter_accuracy[&'t'], 0.0);
+        assert!(!app.user_data.letter_time_count.contains_key(&'t'));
+        // Every wrong key is still a confusion
+        assert_eq!(app.user_data.letter_confusions[&'t'], HashMap::from([('r', 2), ('y', 1)]));
+    }
+
+    #[test]
+    fn enter_types_the_separator_only_when_enabled() {
+        assert_eq!(enter_key(KeyCode::Enter, true, ' '), KeyCode::Char(' '));
+        assert_eq!(enter_key(KeyCode::Enter, true, ','), KeyCode::Char(','));
+        assert_eq!(enter_key(KeyCode::Enter, false, ' '), KeyCode::Enter);
+        // A newline separator can only be typed with Enter
+        assert_eq!(enter_key(KeyCode::Enter, false, '\n'), KeyCode::Char('\n'));
+        assert_eq!(enter_key(KeyCode::Char('a'), true, ' '), KeyCode::Char('a'));
+    }
+
+    // Plenty of samples for both letters: 'a' always right, 'q' right 40% of the time
+    fn app_with_weak_q() -> AppState {
+        let mut app = test_app();
+        app.words_list = vec!["aaaa".to_string(), "qqqq".to_string()];
+        for (ch, correct) in [('a', 100), ('q', 40)] {
+            app.user_data.letter_shown.insert(ch, 100);
+            app.user_data.letter_correct.insert(ch, correct);
+        }
+        app
+    }
+
+    #[test]
+    fn common_words_come_in_frequency_order() {
+        let mut app = test_app();
+        app.words_list = ["zebra", "of", "quartz", "the", "be"].iter().map(|w| w.to_string()).collect();
+        app.in_order = Some(0);
+        assert_eq!(app.pick_text(5), "the be of zebra quartz");
+        assert_eq!(app.pick_text(2), "the be");
+    }
+
+    fn share_of(text: &str, word: &str) -> f64 {
+        let words: Vec<&str> = text.split(' ').collect();
+        words.iter().filter(|w| **w == word).count() as f64 / words.len() as f64
+    }
+
+    #[test]
+    fn accuracy_exponent_targets_weak_letters_harder() {
+        let mut app = app_with_weak_q();
+        let ratio = |app: &AppState| {
+            let weights = app.letter_weights();
+            weights[&'q'] / weights[&'a']
+        };
+        let mild = ratio(&app);
+        let mild_share = share_of(&app.get_weighted_words(2000), "qqqq");
+
+        app.settings.accuracy_exponent = 4.0;
+        app.rng = StdRng::seed_from_u64(7);
+        assert!(ratio(&app) > mild);
+        assert!(share_of(&app.get_weighted_words(2000), "qqqq") > mild_share);
+    }
+}